@@ -3,6 +3,72 @@
 
 -- Configuration table
 config = {
+    -- Layout configuration
+    layout = {
+        -- Resize windows to fill their chessboard cell and mark them as tiled
+        tiled_sizing = false,
+        -- Distance in pixels the "pan" action moves the viewport
+        pan_step = 200,
+        -- Scroll the viewport to the focused window: "off", "edge" or "center"
+        follow_focus = "edge",
+        -- Window moving into the empty center cell: "recent", "spiral" or "none"
+        center_refill = "recent",
+        -- Board new windows join: "pointer" (output under the pointer) or "focus" (board of the focused window)
+        new_window_output = "pointer",
+        -- Cell of a minimized window: "release" (free for other windows) or "reserve" (kept until restored)
+        minimized_cell = "release",
+        -- Another window going fullscreen on the same output: "stack" (covers the previous one)
+        -- or "replace" (the previous one returns to its cell)
+        fullscreen_policy = "stack",
+    },
+
+    focus = {
+        -- Focus the window the pointer enters, after the pointer stayed in it for the delay in milliseconds
+        follows_mouse = false,
+        follows_mouse_delay = 0,
+        -- Move the pointer to the center of a window focused with the keyboard
+        mouse_follows_focus = false,
+        -- Which activation requests may take the focus: "strict" (only from the focused app),
+        -- "smart" (shortly after an input event) or "off" (all), refused ones mark the window urgent
+        stealing_prevention = "smart",
+    },
+
+    border = {
+        -- Border drawn around every window, in logical pixels (0 disables it)
+        width = 2,
+        -- RGBA colors of the focused window, the other windows and windows wanting attention
+        focused = { 0.45, 0.55, 0.85, 1.0 },
+        unfocused = { 0.3, 0.3, 0.32, 1.0 },
+        urgent = { 1.0, 0.6, 0.2, 1.0 },
+    },
+
+    effects = {
+        -- Rounded window corners, radius in logical pixels (0 keeps them square)
+        corner_radius = 8,
+        -- Drop shadow fading out over shadow_size logical pixels (0 disables it), offset and RGBA color
+        shadow_size = 16,
+        shadow_offset = { 0, 4 },
+        shadow_color = { 0.0, 0.0, 0.0, 0.5 },
+        -- Opacity factor applied to every unfocused window (1.0 disables dimming)
+        inactive_dim = 1.0,
+    },
+
+    -- Window rules, matched against the app_id and title (WM_CLASS and WM_NAME for X11 windows).
+    -- Patterns are regular expressions, later rules override properties set by earlier ones.
+    -- Properties: floating, size = { w, h }, position = { x, y }, output, cell, ssd,
+    -- fullscreen, fake_fullscreen, opacity, stacking ("above", "normal" or "below"), inhibit_idle, no_focus,
+    -- activation (always grant or refuse activation requests)
+    rules = {
+        { app_id = "^pavucontrol$", floating = true, size = { 800, 500 } },
+        { title = "^Picture-in-Picture$", floating = true, no_focus = true },
+    },
+
+    -- Scratchpads, shown and hidden again by the "scratchpad" action with args.name.
+    -- The window is matched by app_id (regular expression), the command starts it if none exists.
+    scratchpads = {
+        term = { app_id = "^scratch-term$", command = "foot --app-id scratch-term" },
+    },
+
     -- Keybindings configuration
     keybindings = {
         -- Launch terminal with Logo+Return
@@ -67,6 +133,87 @@ config = {
             action = "focus",
             args = { direction = "down" }
         },
+
+        -- Swap the focused window with its neighbour with Logo+Shift+arrow keys
+        {
+            modifiers = {"Logo", "Shift"},
+            key = "Left",
+            action = "swap",
+            args = { direction = "left" }
+        },
+        {
+            modifiers = {"Logo", "Shift"},
+            key = "Right",
+            action = "swap",
+            args = { direction = "right" }
+        },
+        {
+            modifiers = {"Logo", "Shift"},
+            key = "Up",
+            action = "swap",
+            args = { direction = "up" }
+        },
+        {
+            modifiers = {"Logo", "Shift"},
+            key = "Down",
+            action = "swap",
+            args = { direction = "down" }
+        },
+
+        -- Move the focused window into the center cell with Logo+Shift+Return
+        {
+            modifiers = {"Logo", "Shift"},
+            key = "Return",
+            action = "promote"
+        },
+
+        -- Show or hide the scratchpad terminal with Logo+S
+        {
+            modifiers = {"Logo"},
+            key = "s",
+            action = "scratchpad",
+            args = { name = "term" }
+        },
+
+        -- Pan the viewport across the board with Logo+Ctrl+arrow keys
+        {
+            modifiers = {"Logo", "Ctrl"},
+            key = "Left",
+            action = "pan",
+            args = { direction = "left" }
+        },
+        {
+            modifiers = {"Logo", "Ctrl"},
+            key = "Right",
+            action = "pan",
+            args = { direction = "right" }
+        },
+        {
+            modifiers = {"Logo", "Ctrl"},
+            key = "Up",
+            action = "pan",
+            args = { direction = "up" }
+        },
+        {
+            modifiers = {"Logo", "Ctrl"},
+            key = "Down",
+            action = "pan",
+            args = { direction = "down" }
+        },
+
+        -- Make the focused window more transparent or more opaque with Logo+minus / Logo+equal
+        {
+            modifiers = {"Logo"},
+            key = "minus",
+            action = "opacity",
+            args = { change = "-0.1" }
+        },
+        {
+            modifiers = {"Logo"},
+            key = "equal",
+            action = "opacity",
+            args = { change = "0.1" }
+        },
     }
 }
 
@@ -0,0 +1,35 @@
+// Solid color with the corners of the window clipped to a radius, used for the header bar
+precision mediump float;
+
+uniform vec2 size;
+uniform float alpha;
+varying vec2 v_coords;
+
+#if defined(DEBUG_FLAGS)
+uniform float tint;
+#endif
+
+uniform vec4 color;
+// window geometry relative to the element: x, y, width, height
+uniform vec4 geometry;
+uniform float radius;
+
+float corner_coverage(vec2 pos) {
+    vec2 p = pos - geometry.xy;
+    if (p.x < 0.0 || p.y < 0.0 || p.x > geometry.z || p.y > geometry.w) {
+        return 1.0;
+    }
+    vec2 center = clamp(p, vec2(radius), geometry.zw - vec2(radius));
+    return clamp(radius - distance(p, center) + 0.5, 0.0, 1.0);
+}
+
+void main() {
+    vec4 result = color * alpha * corner_coverage(v_coords * size);
+
+#if defined(DEBUG_FLAGS)
+    if (tint == 1.0)
+        result = vec4(0.0, 0.3, 0.0, 0.2) + result * 0.8;
+#endif
+
+    gl_FragColor = result;
+}
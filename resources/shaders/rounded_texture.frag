@@ -0,0 +1,53 @@
+// Default texture shader with the corners of the window clipped to a radius
+precision mediump float;
+#if defined(EXTERNAL)
+#extension GL_OES_EGL_image_external : require
+#endif
+
+#if defined(EXTERNAL)
+uniform samplerExternalOES tex;
+#else
+uniform sampler2D tex;
+#endif
+
+uniform float alpha;
+varying vec2 v_coords;
+
+#if defined(DEBUG_FLAGS)
+uniform float tint;
+#endif
+
+// size of the element in physical pixels
+uniform vec2 element_size;
+// window geometry relative to the element: x, y, width, height
+uniform vec4 geometry;
+uniform float radius;
+
+float corner_coverage(vec2 pos) {
+    vec2 p = pos - geometry.xy;
+    // leave everything outside the window alone, e.g. popups
+    if (p.x < 0.0 || p.y < 0.0 || p.x > geometry.z || p.y > geometry.w) {
+        return 1.0;
+    }
+    vec2 center = clamp(p, vec2(radius), geometry.zw - vec2(radius));
+    return clamp(radius - distance(p, center) + 0.5, 0.0, 1.0);
+}
+
+void main() {
+    vec4 color = texture2D(tex, v_coords);
+
+#if defined(NO_ALPHA)
+    color = vec4(color.rgb, 1.0) * alpha;
+#else
+    color = color * alpha;
+#endif
+
+    color = color * corner_coverage(v_coords * element_size);
+
+#if defined(DEBUG_FLAGS)
+    if (tint == 1.0)
+        color = vec4(0.0, 0.3, 0.0, 0.2) + color * 0.8;
+#endif
+
+    gl_FragColor = color;
+}
@@ -0,0 +1,36 @@
+// Soft shadow of a rounded rectangle
+precision mediump float;
+
+uniform vec2 size;
+uniform float alpha;
+varying vec2 v_coords;
+
+#if defined(DEBUG_FLAGS)
+uniform float tint;
+#endif
+
+uniform vec4 color;
+// rectangle casting the shadow relative to the element: x, y, width, height
+uniform vec4 rect;
+uniform float radius;
+// distance over which the shadow fades out
+uniform float blur;
+
+float rounded_box_distance(vec2 pos, vec2 half_size, float r) {
+    vec2 q = abs(pos) - half_size + r;
+    return length(max(q, 0.0)) + min(max(q.x, q.y), 0.0) - r;
+}
+
+void main() {
+    vec2 pos = v_coords * size;
+    float dist = rounded_box_distance(pos - (rect.xy + rect.zw / 2.0), rect.zw / 2.0, radius);
+    float strength = 1.0 - smoothstep(-blur / 2.0, blur, dist);
+    vec4 result = color * alpha * strength;
+
+#if defined(DEBUG_FLAGS)
+    if (tint == 1.0)
+        result = vec4(0.0, 0.3, 0.0, 0.2) + result * 0.8;
+#endif
+
+    gl_FragColor = result;
+}
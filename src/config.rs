@@ -1,13 +1,16 @@
 use std::collections::HashMap;
 use std::fs::File;
 use std::io::Read;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
 
 use mlua::{Lua, LuaSerdeExt, Result as LuaResult, Value as LuaValue};
+use once_cell::sync::Lazy;
+use regex::Regex;
 use serde::{Deserialize, Serialize};
 use smithay::input::keyboard::keysyms;
 use smithay::reexports::wayland_server::protocol::wl_seat::KeyState;
-use tracing::{debug, error, info};
+use tracing::{debug, error, info, warn};
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct KeyBinding {
@@ -17,9 +20,326 @@ pub struct KeyBinding {
     pub args: Option<HashMap<String, String>>,
 }
 
+// 全局配置变量
+pub static GLOBAL_CONFIG: Lazy<RwLock<Option<Config>>> = Lazy::new(|| RwLock::new(None));
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct LayoutConfig {
+    /// Configure toplevels with the size of their board cell and mark them as tiled
+    pub tiled_sizing: bool,
+    /// Distance in logical pixels an output is panned by a single `pan` action
+    pub pan_step: i32,
+    /// How the viewport follows the keyboard focus
+    pub follow_focus: FollowFocus,
+    /// Which window moves into the center cell once it is vacated
+    pub center_refill: CenterRefill,
+    /// Which board new windows join
+    pub new_window_output: NewWindowOutput,
+    /// What happens to the cell of a minimized window
+    pub minimized_cell: MinimizedCell,
+    /// What happens to the fullscreen window of an output when another one goes fullscreen there
+    pub fullscreen_policy: FullscreenPolicy,
+}
+
+/// Fullscreen windows sharing an output
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FullscreenPolicy {
+    /// Cover the previous one, it is shown again once the new one leaves fullscreen
+    #[default]
+    Stack,
+    /// Unfullscreen the previous one, it returns to its cell
+    Replace,
+}
+
+/// Cell of a window while it is minimized
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MinimizedCell {
+    /// Free the cell for other windows, the window gets a new one when it is restored
+    #[default]
+    Release,
+    /// Keep the cell empty until the window is restored
+    Reserve,
+}
+
+/// Output whose board a new window is placed on
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NewWindowOutput {
+    /// The output under the pointer
+    #[default]
+    Pointer,
+    /// The board of the focused window, or the output under the pointer if nothing is focused
+    Focus,
+}
+
+/// Window taking over the center cell after the center window went away
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CenterRefill {
+    /// The most recently focused window
+    #[default]
+    Recent,
+    /// The window in the lowest cell of the spiral
+    Spiral,
+    /// Leave the center empty
+    None,
+}
+
+/// Scrolling of the viewport when a window gets keyboard focus
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FollowFocus {
+    /// Never pan automatically
+    #[default]
+    Off,
+    /// Pan just enough to make the focused window fully visible
+    Edge,
+    /// Pan to center the focused window
+    Center,
+}
+
+impl Default for LayoutConfig {
+    fn default() -> Self {
+        LayoutConfig {
+            tiled_sizing: false,
+            pan_step: 200,
+            follow_focus: FollowFocus::default(),
+            center_refill: CenterRefill::default(),
+            new_window_output: NewWindowOutput::default(),
+            minimized_cell: MinimizedCell::default(),
+            fullscreen_policy: FullscreenPolicy::default(),
+        }
+    }
+}
+
+/// Layer a window is stacked in, windows are only raised within their class
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StackingClass {
+    /// Always on top of normal windows
+    Above,
+    #[default]
+    Normal,
+    /// Always below normal windows
+    Below,
+}
+
+pub fn parse_stacking_class(class: &str) -> Option<StackingClass> {
+    match class.to_lowercase().as_str() {
+        "above" => Some(StackingClass::Above),
+        "normal" => Some(StackingClass::Normal),
+        "below" => Some(StackingClass::Below),
+        _ => None,
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    Left,
+    Right,
+    Up,
+    Down,
+}
+
+impl Direction {
+    /// Unit step in this direction
+    pub fn delta(self) -> (i32, i32) {
+        match self {
+            Direction::Left => (-1, 0),
+            Direction::Right => (1, 0),
+            Direction::Up => (0, -1),
+            Direction::Down => (0, 1),
+        }
+    }
+}
+
+pub fn parse_direction(direction: &str) -> Option<Direction> {
+    match direction.to_lowercase().as_str() {
+        "left" => Some(Direction::Left),
+        "right" => Some(Direction::Right),
+        "up" => Some(Direction::Up),
+        "down" => Some(Direction::Down),
+        _ => None,
+    }
+}
+
+/// Regular expression matched against app_ids and titles, compiled when the config is loaded.
+///
+/// An invalid pattern is reported once and matches nothing.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(from = "String", into = "String")]
+pub struct Pattern {
+    source: String,
+    regex: Option<Regex>,
+}
+
+impl Pattern {
+    pub fn is_match(&self, value: &str) -> bool {
+        self.regex.as_ref().is_some_and(|regex| regex.is_match(value))
+    }
+}
+
+impl From<String> for Pattern {
+    fn from(source: String) -> Self {
+        let regex = Regex::new(&source)
+            .map_err(|err| warn!(pattern = source, "Invalid window rule pattern: {}", err))
+            .ok();
+        Pattern { source, regex }
+    }
+}
+
+impl From<Pattern> for String {
+    fn from(pattern: Pattern) -> Self {
+        pattern.source
+    }
+}
+
+/// Properties given to windows matching the app_id and title patterns.
+///
+/// For X11 windows the patterns are matched against WM_CLASS and WM_NAME.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct WindowRule {
+    /// Regex the app_id has to match
+    pub app_id: Option<Pattern>,
+    /// Regex the title has to match
+    pub title: Option<Pattern>,
+    pub floating: Option<bool>,
+    /// Width and height of a floating window
+    pub size: Option<[i32; 2]>,
+    /// Position of a floating window relative to its output
+    pub position: Option<[i32; 2]>,
+    /// Connector name of the output whose board the window joins
+    pub output: Option<String>,
+    /// Spiral index of the cell the window is placed in, if it is free
+    pub cell: Option<usize>,
+    /// Force server side decorations on or off
+    pub ssd: Option<bool>,
+    pub fullscreen: Option<bool>,
+    /// Tell the window it is fullscreen when it asks, but keep it in its cell
+    pub fake_fullscreen: Option<bool>,
+    pub opacity: Option<f32>,
+    /// Stacking class: "above", "normal" or "below"
+    pub stacking: Option<StackingClass>,
+    /// Keep the session from going idle while the window is mapped
+    pub inhibit_idle: Option<bool>,
+    /// Never give the window keyboard focus
+    pub no_focus: Option<bool>,
+    /// Always grant (or always refuse) activation requests, regardless of `focus.stealing_prevention`
+    pub activation: Option<bool>,
+}
+
+/// Window shown and hidden again by the `scratchpad` action
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct ScratchpadConfig {
+    /// Regex the app_id (WM_CLASS for X11 windows) of the window has to match
+    pub app_id: Pattern,
+    /// Command started when no matching window exists yet
+    pub command: Option<String>,
+}
+
+/// How the keyboard focus and the pointer follow each other
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct FocusConfig {
+    /// Focus a window once the pointer enters it
+    pub follows_mouse: bool,
+    /// Milliseconds the pointer has to stay in a window before it gets focus
+    pub follows_mouse_delay: u64,
+    /// Move the pointer to the center of a window focused with the keyboard
+    pub mouse_follows_focus: bool,
+    /// Which xdg-activation requests may take the focus, refused ones mark the window urgent
+    pub stealing_prevention: FocusStealing,
+}
+
+/// Border drawn around every window, colored by its focus state
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct BorderConfig {
+    /// Width in logical pixels, 0 disables the border
+    pub width: i32,
+    /// RGBA colors
+    pub focused: [f32; 4],
+    pub unfocused: [f32; 4],
+    /// Color of urgent windows, blinking like the urgent header bar
+    pub urgent: [f32; 4],
+}
+
+impl Default for BorderConfig {
+    fn default() -> Self {
+        BorderConfig {
+            width: 0,
+            focused: [0.45, 0.55, 0.85, 1.0],
+            unfocused: [0.3, 0.3, 0.32, 1.0],
+            urgent: [1.0, 0.6, 0.2, 1.0],
+        }
+    }
+}
+
+/// Rounded corners, drop shadows and dimming of the windows.
+///
+/// Corners and shadows are drawn by the GLES renderers only, fullscreen windows have neither.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct EffectsConfig {
+    /// Radius in logical pixels the window corners are rounded with, 0 keeps them square
+    pub corner_radius: i32,
+    /// Distance in logical pixels the shadow fades out over, 0 disables shadows
+    pub shadow_size: i32,
+    /// Offset of the shadow from the window
+    pub shadow_offset: [i32; 2],
+    /// RGBA color of the shadow
+    pub shadow_color: [f32; 4],
+    /// Factor the opacity of unfocused windows is multiplied with, 1.0 doesn't dim them
+    pub inactive_dim: f32,
+}
+
+impl Default for EffectsConfig {
+    fn default() -> Self {
+        EffectsConfig {
+            corner_radius: 0,
+            shadow_size: 0,
+            shadow_offset: [0, 4],
+            shadow_color: [0.0, 0.0, 0.0, 0.5],
+            inactive_dim: 1.0,
+        }
+    }
+}
+
+/// Focus stealing prevention for xdg-activation requests
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FocusStealing {
+    /// Only tokens the focused client created shortly after an input event
+    Strict,
+    /// Tokens created shortly after an input event, by any client
+    #[default]
+    Smart,
+    /// Every request takes the focus
+    Off,
+}
+
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct Config {
     pub keybindings: Vec<KeyBinding>,
+    #[serde(default)]
+    pub layout: LayoutConfig,
+    #[serde(default)]
+    pub focus: FocusConfig,
+    #[serde(default)]
+    pub border: BorderConfig,
+    #[serde(default)]
+    pub effects: EffectsConfig,
+    /// Window rules, later rules override properties set by earlier ones
+    #[serde(default)]
+    pub rules: Vec<WindowRule>,
+    /// Scratchpad windows by name
+    #[serde(default)]
+    pub scratchpads: HashMap<String, ScratchpadConfig>,
 }
 
 impl Default for Config {
@@ -43,10 +363,57 @@ impl Default for Config {
                     args: None,
                 },
             ],
+            layout: LayoutConfig::default(),
+            focus: FocusConfig::default(),
+            border: BorderConfig::default(),
+            effects: EffectsConfig::default(),
+            rules: Vec::new(),
+            scratchpads: HashMap::new(),
         }
     }
 }
 
+/// Runs `f` with the loaded config, borrowed under the read lock instead of copied
+pub fn with_config<T>(f: impl FnOnce(&Config) -> T) -> T {
+    match GLOBAL_CONFIG.read().unwrap().as_ref() {
+        Some(config) => f(config),
+        None => f(&Config::default()),
+    }
+}
+
+/// Layout settings of the loaded config, without copying the rest of it
+pub fn layout() -> LayoutConfig {
+    with_config(|config| config.layout.clone())
+}
+
+/// Focus settings of the loaded config, without copying the rest of it
+pub fn focus() -> FocusConfig {
+    with_config(|config| config.focus.clone())
+}
+
+/// Border settings of the loaded config, without copying the rest of it
+pub fn border() -> BorderConfig {
+    with_config(|config| config.border.clone())
+}
+
+/// Effect settings of the loaded config, without copying the rest of it
+pub fn effects() -> EffectsConfig {
+    with_config(|config| config.effects.clone())
+}
+
+/// Location of the config file, `$ANVIL_CONFIG` or `anvil/config.lua` in the XDG config directory
+pub fn config_path() -> PathBuf {
+    if let Some(path) = std::env::var_os("ANVIL_CONFIG") {
+        return PathBuf::from(path);
+    }
+    std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .filter(|path| path.is_absolute())
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+        .map(|config_home| config_home.join("anvil").join("config.lua"))
+        .unwrap_or_else(|| PathBuf::from("config.lua"))
+}
+
 pub fn load_config(config_path: &Path) -> Config {
     if !config_path.exists() {
         info!("Config file not found, using default config");
@@ -97,6 +464,10 @@ pub fn parse_keysym(key: &str) -> Option<keysyms::Keysym> {
         "backspace" => Some(keysyms::BackSpace),
         "tab" => Some(keysyms::Tab),
         "space" => Some(keysyms::space),
+        "left" => Some(keysyms::Left),
+        "right" => Some(keysyms::Right),
+        "up" => Some(keysyms::Up),
+        "down" => Some(keysyms::Down),
         "a" => Some(keysyms::a),
         "b" => Some(keysyms::b),
         "c" => Some(keysyms::c),
@@ -133,6 +504,17 @@ pub fn parse_keysym(key: &str) -> Option<keysyms::Keysym> {
         "8" => Some(keysyms::_8),
         "9" => Some(keysyms::_9),
         "0" => Some(keysyms::_0),
+        "minus" | "-" => Some(keysyms::minus),
+        "equal" | "=" => Some(keysyms::equal),
+        "comma" | "," => Some(keysyms::comma),
+        "period" | "." => Some(keysyms::period),
+        "slash" | "/" => Some(keysyms::slash),
+        "backslash" | "\\" => Some(keysyms::backslash),
+        "semicolon" | ";" => Some(keysyms::semicolon),
+        "apostrophe" | "'" => Some(keysyms::apostrophe),
+        "grave" | "`" => Some(keysyms::grave),
+        "bracketleft" | "[" => Some(keysyms::bracketleft),
+        "bracketright" | "]" => Some(keysyms::bracketright),
         _ => None,
     }
 }
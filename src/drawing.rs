@@ -1,27 +1,28 @@
 #![allow(clippy::too_many_arguments)]
 
+#[cfg(feature = "udev")]
+use smithay::backend::renderer::pixman::PixmanRenderer;
+#[cfg(feature = "debug")]
+use smithay::{backend::renderer::Frame, utils::Logical};
 use smithay::{
     backend::renderer::{
         element::{
             memory::{MemoryRenderBuffer, MemoryRenderBufferRenderElement},
             surface::WaylandSurfaceRenderElement,
-            AsRenderElements, Kind,
+            AsRenderElements, Element, Id, Kind, RenderElement,
+        },
+        gles::{
+            GlesError, GlesFrame, GlesPixelProgram, GlesRenderer, GlesTexProgram, Uniform, UniformName,
+            UniformType,
         },
+        utils::{CommitCounter, DamageSet, OpaqueRegions},
         Color32F, ImportAll, ImportMem, Renderer, Texture,
     },
     input::pointer::CursorImageStatus,
     render_elements,
-    utils::{Physical, Point, Scale},
-};
-#[cfg(feature = "debug")]
-use smithay::{
-    backend::renderer::{
-        element::{Element, Id, RenderElement},
-        utils::CommitCounter,
-        Frame,
-    },
-    utils::{Buffer, Logical, Rectangle, Size, Transform},
+    utils::{Buffer, Physical, Point, Rectangle, Scale, Size, Transform},
 };
+use tracing::warn;
 
 pub static CLEAR_COLOR: Color32F = Color32F::new(0.8, 0.8, 0.9, 1.0);
 pub static CLEAR_COLOR_FULLSCREEN: Color32F = Color32F::new(0.0, 0.0, 0.0, 0.0);
@@ -254,3 +255,404 @@ where
         Ok(())
     }
 }
+
+static ROUNDED_TEXTURE_SHADER: &str = include_str!("../resources/shaders/rounded_texture.frag");
+static ROUNDED_SOLID_SHADER: &str = include_str!("../resources/shaders/rounded_solid.frag");
+static SHADOW_SHADER: &str = include_str!("../resources/shaders/shadow.frag");
+
+/// Renderers the window effects (rounded corners and shadows) are drawn with.
+///
+/// Only GLES renderers run the effect shaders, the others skip the effects.
+pub trait EffectsRenderer: Renderer {
+    /// Shaders of the effects, none if the renderer can't run them
+    fn effect_shaders(&mut self) -> Option<EffectShaders>;
+
+    /// Runs `draw` with the GLES frame behind `frame`
+    fn with_gles_frame<'frame, 'buffer>(
+        frame: &mut Self::Frame<'frame, 'buffer>,
+        draw: impl FnOnce(&mut GlesFrame<'frame, 'buffer>) -> Result<(), GlesError>,
+    ) -> Result<(), Self::Error>;
+}
+
+impl EffectsRenderer for GlesRenderer {
+    fn effect_shaders(&mut self) -> Option<EffectShaders> {
+        EffectShaders::get(self)
+    }
+
+    fn with_gles_frame<'frame, 'buffer>(
+        frame: &mut Self::Frame<'frame, 'buffer>,
+        draw: impl FnOnce(&mut GlesFrame<'frame, 'buffer>) -> Result<(), GlesError>,
+    ) -> Result<(), Self::Error> {
+        draw(frame)
+    }
+}
+
+#[cfg(feature = "udev")]
+impl EffectsRenderer for PixmanRenderer {
+    fn effect_shaders(&mut self) -> Option<EffectShaders> {
+        None
+    }
+
+    fn with_gles_frame<'frame, 'buffer>(
+        _frame: &mut Self::Frame<'frame, 'buffer>,
+        _draw: impl FnOnce(&mut GlesFrame<'frame, 'buffer>) -> Result<(), GlesError>,
+    ) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
+
+/// Effect shaders compiled for a renderer
+#[derive(Debug, Clone)]
+pub struct EffectShaders {
+    rounded_texture: GlesTexProgram,
+    rounded_solid: GlesPixelProgram,
+    shadow: GlesPixelProgram,
+}
+
+/// Shaders of a GLES context, none if they failed to compile
+struct CompiledEffectShaders(Option<EffectShaders>);
+
+impl EffectShaders {
+    /// Shaders of the renderer, compiled on first use
+    pub fn get(renderer: &mut GlesRenderer) -> Option<EffectShaders> {
+        if let Some(compiled) = renderer.egl_context().user_data().get::<CompiledEffectShaders>() {
+            return compiled.0.clone();
+        }
+        let shaders = EffectShaders::compile(renderer)
+            .map_err(|err| warn!("Failed to compile the effect shaders: {}", err))
+            .ok();
+        renderer
+            .egl_context()
+            .user_data()
+            .insert_if_missing(|| CompiledEffectShaders(shaders.clone()));
+        shaders
+    }
+
+    fn compile(renderer: &mut GlesRenderer) -> Result<EffectShaders, GlesError> {
+        let rounded_texture = renderer.compile_custom_texture_shader(
+            ROUNDED_TEXTURE_SHADER,
+            &[
+                UniformName::new("element_size", UniformType::_2f),
+                UniformName::new("geometry", UniformType::_4f),
+                UniformName::new("radius", UniformType::_1f),
+            ],
+        )?;
+        let rounded_solid = renderer.compile_custom_pixel_shader(
+            ROUNDED_SOLID_SHADER,
+            &[
+                UniformName::new("color", UniformType::_4f),
+                UniformName::new("geometry", UniformType::_4f),
+                UniformName::new("radius", UniformType::_1f),
+            ],
+        )?;
+        let shadow = renderer.compile_custom_pixel_shader(
+            SHADOW_SHADER,
+            &[
+                UniformName::new("color", UniformType::_4f),
+                UniformName::new("rect", UniformType::_4f),
+                UniformName::new("radius", UniformType::_1f),
+                UniformName::new("blur", UniformType::_1f),
+            ],
+        )?;
+        Ok(EffectShaders {
+            rounded_texture,
+            rounded_solid,
+            shadow,
+        })
+    }
+}
+
+/// Rectangle as a shader uniform, stretched by `ratio` for elements drawn at another size
+fn rect_uniform(rect: Rectangle<i32, Physical>, ratio: (f32, f32)) -> (f32, f32, f32, f32) {
+    (
+        rect.loc.x as f32 * ratio.0,
+        rect.loc.y as f32 * ratio.1,
+        rect.size.w as f32 * ratio.0,
+        rect.size.h as f32 * ratio.1,
+    )
+}
+
+/// Ratio between the size an element is drawn at and its own size, e.g. in thumbnails
+fn draw_ratio(dst: Rectangle<i32, Physical>, size: Size<i32, Physical>) -> (f32, f32) {
+    (
+        dst.size.w as f32 / size.w.max(1) as f32,
+        dst.size.h as f32 / size.h.max(1) as f32,
+    )
+}
+
+/// Color with its RGB components multiplied by its alpha, the way the shaders blend colors
+fn premultiplied([r, g, b, a]: [f32; 4]) -> [f32; 4] {
+    [r * a, g * a, b * a, a]
+}
+
+/// Element with the corners of its window clipped to a radius.
+///
+/// Surfaces are drawn with the rounded texture shader, solid colors (the header bar)
+/// with a pixel shader filling the rounded shape. The clipped corners are left out
+/// of the opaque regions, and the element is never scanned out directly.
+#[derive(Debug)]
+pub struct RoundedElement<E> {
+    inner: E,
+    shaders: EffectShaders,
+    /// Size of the element when it was created
+    size: Size<i32, Physical>,
+    /// Window geometry relative to the element
+    window_geometry: Rectangle<i32, Physical>,
+    radius: i32,
+    /// Color of a solid element, premultiplied with its alpha
+    fill: Option<[f32; 4]>,
+}
+
+impl<E: Element> RoundedElement<E> {
+    /// `window_geometry` is in the coordinates the element was created in
+    pub fn new(
+        inner: E,
+        shaders: EffectShaders,
+        window_geometry: Rectangle<i32, Physical>,
+        radius: i32,
+        scale: Scale<f64>,
+    ) -> Self {
+        let geometry = inner.geometry(scale);
+        RoundedElement {
+            inner,
+            shaders,
+            size: geometry.size,
+            window_geometry: Rectangle::new(window_geometry.loc - geometry.loc, window_geometry.size),
+            radius,
+            fill: None,
+        }
+    }
+
+    /// Rounded solid color element, `color` and `alpha` have to be the ones the element was created with
+    pub fn solid(
+        inner: E,
+        shaders: EffectShaders,
+        window_geometry: Rectangle<i32, Physical>,
+        radius: i32,
+        scale: Scale<f64>,
+        color: [f32; 4],
+        alpha: f32,
+    ) -> Self {
+        RoundedElement {
+            fill: Some(premultiplied(color).map(|component| component * alpha)),
+            ..RoundedElement::new(inner, shaders, window_geometry, radius, scale)
+        }
+    }
+
+    /// Corners of the window clipped away, relative to the element
+    fn clipped_corners(&self) -> [Rectangle<i32, Physical>; 4] {
+        let Rectangle { loc, size: geo } = self.window_geometry;
+        let size = Size::from((self.radius, self.radius));
+        [
+            Rectangle::new(loc, size),
+            Rectangle::new(loc + Point::from((geo.w - self.radius, 0)), size),
+            Rectangle::new(loc + Point::from((0, geo.h - self.radius)), size),
+            Rectangle::new(
+                loc + Point::from((geo.w - self.radius, geo.h - self.radius)),
+                size,
+            ),
+        ]
+    }
+}
+
+impl<E: Element> Element for RoundedElement<E> {
+    fn id(&self) -> &Id {
+        self.inner.id()
+    }
+
+    fn current_commit(&self) -> CommitCounter {
+        self.inner.current_commit()
+    }
+
+    fn location(&self, scale: Scale<f64>) -> Point<i32, Physical> {
+        self.inner.location(scale)
+    }
+
+    fn src(&self) -> Rectangle<f64, Buffer> {
+        self.inner.src()
+    }
+
+    fn transform(&self) -> Transform {
+        self.inner.transform()
+    }
+
+    fn geometry(&self, scale: Scale<f64>) -> Rectangle<i32, Physical> {
+        self.inner.geometry(scale)
+    }
+
+    fn damage_since(&self, scale: Scale<f64>, commit: Option<CommitCounter>) -> DamageSet<i32, Physical> {
+        self.inner.damage_since(scale, commit)
+    }
+
+    fn opaque_regions(&self, scale: Scale<f64>) -> OpaqueRegions<i32, Physical> {
+        let regions = self.inner.opaque_regions(scale);
+        Rectangle::subtract_rects_many(regions.iter().copied(), self.clipped_corners())
+            .into_iter()
+            .collect()
+    }
+
+    fn alpha(&self) -> f32 {
+        self.inner.alpha()
+    }
+
+    fn kind(&self) -> Kind {
+        self.inner.kind()
+    }
+}
+
+impl<R, E> RenderElement<R> for RoundedElement<E>
+where
+    R: EffectsRenderer,
+    E: RenderElement<R>,
+{
+    fn draw(
+        &self,
+        frame: &mut R::Frame<'_, '_>,
+        src: Rectangle<f64, Buffer>,
+        dst: Rectangle<i32, Physical>,
+        damage: &[Rectangle<i32, Physical>],
+        opaque_regions: &[Rectangle<i32, Physical>],
+    ) -> Result<(), R::Error> {
+        let ratio = draw_ratio(dst, self.size);
+        let geometry = rect_uniform(self.window_geometry, ratio);
+        let radius = self.radius as f32 * ratio.0;
+
+        if let Some(color) = self.fill {
+            return R::with_gles_frame(frame, |gles_frame| {
+                gles_frame.render_pixel_shader_to(
+                    &self.shaders.rounded_solid,
+                    Rectangle::from_size(Size::from((dst.size.w, dst.size.h))).to_f64(),
+                    dst,
+                    Some(Size::from((dst.size.w, dst.size.h))),
+                    Some(damage),
+                    1.0,
+                    &[
+                        Uniform::new("color", (color[0], color[1], color[2], color[3])),
+                        Uniform::new("geometry", geometry),
+                        Uniform::new("radius", radius),
+                    ],
+                )
+            });
+        }
+
+        let element_size = (dst.size.w as f32, dst.size.h as f32);
+        R::with_gles_frame(frame, |gles_frame| {
+            gles_frame.override_default_tex_program(
+                self.shaders.rounded_texture.clone(),
+                vec![
+                    Uniform::new("element_size", element_size),
+                    Uniform::new("geometry", geometry),
+                    Uniform::new("radius", radius),
+                ],
+            );
+            Ok(())
+        })?;
+        let result = self.inner.draw(frame, src, dst, damage, opaque_regions);
+        R::with_gles_frame(frame, |gles_frame| {
+            gles_frame.clear_tex_program_override();
+            Ok(())
+        })?;
+        result
+    }
+}
+
+/// Soft shadow cast by a window
+#[derive(Debug, Clone)]
+pub struct ShadowElement {
+    id: Id,
+    commit: CommitCounter,
+    shaders: EffectShaders,
+    /// Area the shadow covers, in output coordinates
+    area: Rectangle<i32, Physical>,
+    /// Rectangle casting the shadow, relative to the area
+    rect: Rectangle<i32, Physical>,
+    radius: f32,
+    blur: f32,
+    /// Premultiplied color
+    color: [f32; 4],
+    alpha: f32,
+}
+
+impl ShadowElement {
+    /// `id` and `commit` are kept by the window, the commit has to be bumped
+    /// whenever the shape or color of the shadow changes
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        id: Id,
+        commit: CommitCounter,
+        shaders: EffectShaders,
+        rect: Rectangle<i32, Physical>,
+        radius: i32,
+        blur: i32,
+        color: [f32; 4],
+        alpha: f32,
+    ) -> Self {
+        let area = Rectangle::new(
+            rect.loc - Point::from((blur, blur)),
+            rect.size + Size::from((blur * 2, blur * 2)),
+        );
+        ShadowElement {
+            id,
+            commit,
+            shaders,
+            area,
+            rect: Rectangle::new(Point::from((blur, blur)), rect.size),
+            radius: radius as f32,
+            blur: blur as f32,
+            color: premultiplied(color),
+            alpha,
+        }
+    }
+}
+
+impl Element for ShadowElement {
+    fn id(&self) -> &Id {
+        &self.id
+    }
+
+    fn current_commit(&self) -> CommitCounter {
+        self.commit
+    }
+
+    fn src(&self) -> Rectangle<f64, Buffer> {
+        Rectangle::from_size(Size::from((self.area.size.w, self.area.size.h))).to_f64()
+    }
+
+    fn geometry(&self, _scale: Scale<f64>) -> Rectangle<i32, Physical> {
+        self.area
+    }
+
+    fn alpha(&self) -> f32 {
+        self.alpha
+    }
+}
+
+impl<R: EffectsRenderer> RenderElement<R> for ShadowElement {
+    fn draw(
+        &self,
+        frame: &mut R::Frame<'_, '_>,
+        src: Rectangle<f64, Buffer>,
+        dst: Rectangle<i32, Physical>,
+        damage: &[Rectangle<i32, Physical>],
+        _opaque_regions: &[Rectangle<i32, Physical>],
+    ) -> Result<(), R::Error> {
+        let color = self.color;
+        let ratio = draw_ratio(dst, self.area.size);
+        R::with_gles_frame(frame, |gles_frame| {
+            gles_frame.render_pixel_shader_to(
+                &self.shaders.shadow,
+                src,
+                dst,
+                Some(Size::from((dst.size.w, dst.size.h))),
+                Some(damage),
+                self.alpha,
+                &[
+                    Uniform::new("color", (color[0], color[1], color[2], color[3])),
+                    Uniform::new("rect", rect_uniform(self.rect, ratio)),
+                    Uniform::new("radius", self.radius * ratio.0),
+                    Uniform::new("blur", self.blur * ratio.0),
+                ],
+            )
+        })
+    }
+}
@@ -1,14 +1,17 @@
-use std::{convert::TryInto, process::Command, sync::{atomic::Ordering, RwLock}};
-use once_cell::sync::Lazy;
-use crate::config::Config;
+use std::{convert::TryInto, process::Command, sync::atomic::Ordering};
+use crate::config::{parse_direction, parse_stacking_class, Direction, StackingClass, GLOBAL_CONFIG};
 
-// 全局配置变量
-static GLOBAL_CONFIG: Lazy<RwLock<Option<Config>>> = Lazy::new(|| RwLock::new(None));
-
-use crate::{focus::PointerFocusTarget, shell::FullscreenSurface, AnvilState};
+use crate::{
+    focus::PointerFocusTarget,
+    shell::{cancel_scroll, FullscreenSurface, PointerPanGrab},
+    AnvilState,
+};
 
 #[cfg(feature = "udev")]
-use crate::udev::UdevData;
+use crate::{
+    shell::{layout_to_space, PanGesture},
+    udev::UdevData,
+};
 #[cfg(feature = "udev")]
 use smithay::backend::renderer::DebugFlags;
 
@@ -20,7 +23,7 @@ use smithay::{
     desktop::{layer_map_for_output, WindowSurfaceType},
     input::{
         keyboard::{keysyms as xkb, FilterResult, Keysym, ModifiersState},
-        pointer::{AxisFrame, ButtonEvent, MotionEvent},
+        pointer::{AxisFrame, ButtonEvent, Focus, GrabStartData as PointerGrabStartData, MotionEvent},
     },
     output::Scale,
     reexports::{
@@ -70,8 +73,12 @@ use smithay::{
     },
 };
 
+/// Linux evdev code of the left mouse button
+const BTN_LEFT: u32 = 0x110;
+
 impl<BackendData: Backend> AnvilState<BackendData> {
     fn process_common_key_action(&mut self, action: KeyAction) {
+        let focused = self.focused_window();
         match action {
             KeyAction::None => (),
 
@@ -83,27 +90,98 @@ impl<BackendData: Backend> AnvilState<BackendData> {
             KeyAction::Run(cmd) => {
                 info!(cmd, "Starting program");
 
-                if let Err(e) = Command::new(&cmd)
-                    .envs(
-                        self.socket_name
-                            .clone()
-                            .map(|v| ("WAYLAND_DISPLAY", v))
-                            .into_iter()
-                            .chain(
-                                #[cfg(feature = "xwayland")]
-                                self.xdisplay.map(|v| ("DISPLAY", format!(":{}", v))),
-                                #[cfg(not(feature = "xwayland"))]
-                                None,
-                            ),
-                    )
-                    .spawn()
-                {
+                if let Err(e) = Command::new(&cmd).envs(self.client_env()).spawn() {
                     error!(cmd, err = %e, "Failed to start program");
                 }
             }
 
             KeyAction::TogglePreview => {
-                self.show_window_preview = !self.show_window_preview;
+                self.toggle_overview();
+            }
+
+            KeyAction::Focus(direction) => {
+                self.focus_direction(direction);
+            }
+
+            KeyAction::Swap(direction) => {
+                self.swap_direction(direction, false);
+            }
+
+            KeyAction::Move(direction) => {
+                self.swap_direction(direction, true);
+            }
+
+            KeyAction::Promote => {
+                self.promote();
+            }
+
+            KeyAction::ToggleFloating => {
+                self.toggle_floating();
+            }
+
+            KeyAction::Stacking(class) => {
+                self.toggle_stacking(class);
+            }
+
+            KeyAction::ToggleFullscreen => {
+                self.toggle_fullscreen();
+            }
+
+            KeyAction::Minimize => {
+                if let Some(window) = self.focused_window() {
+                    self.minimize_window(&window);
+                }
+            }
+
+            KeyAction::RestoreLast => {
+                self.restore_last();
+            }
+
+            KeyAction::FocusUrgent => {
+                self.focus_urgent();
+            }
+
+            KeyAction::Opacity(delta) => {
+                self.change_opacity(delta);
+            }
+
+            KeyAction::Scratchpad(name) => {
+                self.toggle_scratchpad(&name);
+            }
+
+            KeyAction::OverviewMove(direction) => {
+                self.overview_move(direction);
+            }
+
+            KeyAction::OverviewSelect => {
+                self.overview_select();
+            }
+
+            KeyAction::SwitchWindow(backwards) => {
+                self.switch_window(backwards);
+            }
+
+            KeyAction::SwitcherSelect => {
+                self.switcher_select();
+            }
+
+            KeyAction::SwitcherCancel => {
+                self.switcher_cancel();
+            }
+
+            KeyAction::Pan(direction) => {
+                let step = crate::config::layout().pan_step;
+                let (dx, dy) = direction.delta();
+                let output = self
+                    .space
+                    .output_under(self.pointer.current_location())
+                    .next()
+                    .or_else(|| self.space.outputs().next())
+                    .cloned();
+                if let Some(output) = output {
+                    cancel_scroll(&output);
+                    self.pan_viewport(&output, (dx * step, dy * step).into());
+                }
             }
 
             KeyAction::ToggleDecorations => {
@@ -137,6 +215,7 @@ impl<BackendData: Backend> AnvilState<BackendData> {
                 action
             ),
         }
+        self.pointer_follows_focus(focused);
     }
 
     fn keyboard_key_to_action<B: InputBackend>(&mut self, evt: B::KeyboardKeyEvent) -> KeyAction {
@@ -180,6 +259,8 @@ impl<BackendData: Backend> AnvilState<BackendData> {
             .map(|inhibitor| inhibitor.is_active())
             .unwrap_or(false);
 
+        let overview = self.overview.is_some();
+        let switcher = self.switcher.is_some();
         let action = keyboard
             .input(self, keycode, state, serial, time, |_, modifiers, handle| {
                 let keysym = handle.modified_sym();
@@ -197,7 +278,25 @@ impl<BackendData: Backend> AnvilState<BackendData> {
                 // so that we can decide on a release if the key
                 // should be forwarded to the client or not.
                 if let KeyState::Pressed = state {
-                    if !inhibited {
+                    if switcher {
+                        // the switcher takes all keys while it is shown
+                        let action = process_keyboard_shortcut(*modifiers, keysym)
+                            .or_else(|| process_switcher_key(keysym))
+                            .unwrap_or(KeyAction::None);
+                        suppressed_keys.push(keysym);
+                        FilterResult::Intercept(action)
+                    } else if overview {
+                        // keys the overview doesn't handle still reach the focused client
+                        match process_keyboard_shortcut(*modifiers, keysym)
+                            .or_else(|| process_overview_key(keysym))
+                        {
+                            Some(action) => {
+                                suppressed_keys.push(keysym);
+                                FilterResult::Intercept(action)
+                            }
+                            None => FilterResult::Forward,
+                        }
+                    } else if !inhibited {
                         let action = process_keyboard_shortcut(*modifiers, keysym);
 
                         if action.is_some() {
@@ -223,6 +322,14 @@ impl<BackendData: Backend> AnvilState<BackendData> {
             .unwrap_or(KeyAction::None);
 
         self.suppressed_keys = suppressed_keys;
+        // letting go of the modifiers the switcher was opened with picks the selected window
+        let released = self
+            .switcher
+            .as_ref()
+            .is_some_and(|switcher| switcher.released(&keyboard.modifier_state()));
+        if state == KeyState::Released && released {
+            return KeyAction::SwitcherSelect;
+        }
         action
     }
 
@@ -232,8 +339,20 @@ impl<BackendData: Backend> AnvilState<BackendData> {
 
         let state = wl_pointer::ButtonState::from(evt.state());
 
-        if wl_pointer::ButtonState::Pressed == state {
-            self.update_keyboard_focus(self.pointer.current_location(), serial);
+        if self.overview.is_some() {
+            if button == BTN_LEFT {
+                self.overview_button(wl_pointer::ButtonState::Pressed == state);
+                return;
+            }
+            // other buttons go to the pointer, it has no focus while the overview covers the
+            // outputs, but buttons held since before the overview opened are released properly
+        } else if wl_pointer::ButtonState::Pressed == state {
+            let logo = self.seat.get_keyboard().unwrap().modifier_state().logo;
+            if logo && button == BTN_LEFT && !self.pointer.is_grabbed() {
+                self.start_pan_grab(button, serial);
+            } else {
+                self.update_keyboard_focus(self.pointer.current_location(), serial);
+            }
         };
         let pointer = self.pointer.clone();
         pointer.button(
@@ -248,6 +367,28 @@ impl<BackendData: Backend> AnvilState<BackendData> {
         pointer.frame(self);
     }
 
+    /// Drags the board under the output the pointer is on along with the pointer
+    fn start_pan_grab(&mut self, button: u32, serial: Serial) {
+        let location = self.pointer.current_location();
+        let Some(output) = self.space.output_under(location).next().cloned() else {
+            return;
+        };
+        cancel_scroll(&output);
+        let initial_output_location = self.space.output_geometry(&output).unwrap().loc;
+
+        let grab = PointerPanGrab {
+            start_data: PointerGrabStartData {
+                focus: None,
+                button,
+                location,
+            },
+            output,
+            initial_output_location,
+        };
+        let pointer = self.pointer.clone();
+        pointer.set_grab(self, grab, serial, Focus::Clear);
+    }
+
     fn update_keyboard_focus(&mut self, location: Point<f64, Logical>, serial: Serial) {
         let keyboard = self.seat.get_keyboard().unwrap();
         let touch = self.seat.get_touch();
@@ -310,7 +451,9 @@ impl<BackendData: Backend> AnvilState<BackendData> {
                 if let Some(surface) = window.0.x11_surface() {
                     self.xwm.as_mut().unwrap().raise_window(surface).unwrap();
                 }
-                keyboard.set_focus(self, Some(window.into()), serial);
+                if window.accepts_focus() {
+                    keyboard.set_focus(self, Some(window.into()), serial);
+                }
                 return;
             }
 
@@ -340,6 +483,11 @@ impl<BackendData: Backend> AnvilState<BackendData> {
         &self,
         pos: Point<f64, Logical>,
     ) -> Option<(PointerFocusTarget, Point<f64, Logical>)> {
+        // the overview covers all outputs and handles the pointer itself
+        if self.overview.is_some() {
+            return None;
+        }
+
         let output = self.space.outputs().find(|o| {
             let geometry = self.space.output_geometry(o).unwrap();
             geometry.contains(pos.to_i32_round())
@@ -447,6 +595,7 @@ impl<BackendData: Backend> AnvilState<BackendData> {
 #[cfg(any(feature = "winit", feature = "x11"))]
 impl<BackendData: Backend> AnvilState<BackendData> {
     pub fn process_input_event_windowed<B: InputBackend>(&mut self, event: InputEvent<B>, output_name: &str) {
+        self.idle_notifier_state.notify_activity(&self.seat);
         match event {
             InputEvent::Keyboard { event } => match self.keyboard_key_to_action::<B>(event) {
                 KeyAction::ScaleUp => {
@@ -511,7 +660,25 @@ impl<BackendData: Backend> AnvilState<BackendData> {
                     | KeyAction::Quit
                     | KeyAction::Run(_)
                     | KeyAction::TogglePreview
-                    | KeyAction::ToggleDecorations => self.process_common_key_action(action),
+                    | KeyAction::ToggleDecorations
+                    | KeyAction::Pan(_)
+                    | KeyAction::Focus(_)
+                    | KeyAction::Swap(_)
+                    | KeyAction::Move(_)
+                    | KeyAction::Promote
+                    | KeyAction::ToggleFloating
+                    | KeyAction::Stacking(_)
+                    | KeyAction::ToggleFullscreen
+                    | KeyAction::Minimize
+                    | KeyAction::RestoreLast
+                    | KeyAction::FocusUrgent
+                    | KeyAction::Opacity(_)
+                    | KeyAction::Scratchpad(_)
+                    | KeyAction::OverviewMove(_)
+                    | KeyAction::OverviewSelect
+                    | KeyAction::SwitchWindow(_)
+                    | KeyAction::SwitcherSelect
+                    | KeyAction::SwitcherCancel => self.process_common_key_action(action),
 
                     _ => tracing::warn!(
                         ?action,
@@ -546,6 +713,7 @@ impl<BackendData: Backend> AnvilState<BackendData> {
         let pos = evt.position_transformed(output_geo.size) + output_geo.loc.to_f64();
         let serial = SCOUNTER.next_serial();
 
+        self.overview_motion(pos);
         let pointer = self.pointer.clone();
         let under = self.surface_under(pos);
         pointer.motion(
@@ -558,6 +726,7 @@ impl<BackendData: Backend> AnvilState<BackendData> {
             },
         );
         pointer.frame(self);
+        self.focus_follows_pointer(pos);
     }
 
     pub fn release_all_keys(&mut self) {
@@ -578,6 +747,7 @@ impl<BackendData: Backend> AnvilState<BackendData> {
 #[cfg(feature = "udev")]
 impl AnvilState<UdevData> {
     pub fn process_input_event<B: InputBackend>(&mut self, dh: &DisplayHandle, event: InputEvent<B>) {
+        self.idle_notifier_state.notify_activity(&self.seat);
         match event {
             InputEvent::Keyboard { event, .. } => match self.keyboard_key_to_action::<B>(event) {
                 #[cfg(feature = "udev")]
@@ -596,7 +766,7 @@ impl AnvilState<UdevData> {
 
                     if let Some(geometry) = geometry {
                         let x = geometry.loc.x as f64 + geometry.size.w as f64 / 2.0;
-                        let y = geometry.size.h as f64 / 2.0;
+                        let y = geometry.loc.y as f64 + geometry.size.h as f64 / 2.0;
                         let location = (x, y).into();
                         let pointer = self.pointer.clone();
                         let under = self.surface_under(location);
@@ -726,7 +896,25 @@ impl AnvilState<UdevData> {
                     | KeyAction::Quit
                     | KeyAction::Run(_)
                     | KeyAction::TogglePreview
-                    | KeyAction::ToggleDecorations => self.process_common_key_action(action),
+                    | KeyAction::ToggleDecorations
+                    | KeyAction::Pan(_)
+                    | KeyAction::Focus(_)
+                    | KeyAction::Swap(_)
+                    | KeyAction::Move(_)
+                    | KeyAction::Promote
+                    | KeyAction::ToggleFloating
+                    | KeyAction::Stacking(_)
+                    | KeyAction::ToggleFullscreen
+                    | KeyAction::Minimize
+                    | KeyAction::RestoreLast
+                    | KeyAction::FocusUrgent
+                    | KeyAction::Opacity(_)
+                    | KeyAction::Scratchpad(_)
+                    | KeyAction::OverviewMove(_)
+                    | KeyAction::OverviewSelect
+                    | KeyAction::SwitchWindow(_)
+                    | KeyAction::SwitcherSelect
+                    | KeyAction::SwitcherCancel => self.process_common_key_action(action),
 
                     _ => unreachable!(),
                 },
@@ -858,6 +1046,7 @@ impl AnvilState<UdevData> {
             }
         }
 
+        self.overview_motion(pointer_location);
         pointer.motion(
             self,
             under,
@@ -868,6 +1057,7 @@ impl AnvilState<UdevData> {
             },
         );
         pointer.frame(self);
+        self.focus_follows_pointer(pointer_location);
 
         // If pointer is now in a constraint region, activate it
         // TODO Anywhere else pointer is moved needs to do this
@@ -906,11 +1096,16 @@ impl AnvilState<UdevData> {
 
         let max_y = self.space.output_geometry(max_h_output).unwrap().size.h;
 
-        let mut pointer_location = (evt.x_transformed(max_x), evt.y_transformed(max_y)).into();
+        // absolute devices span the unpanned output layout
+        let mut pointer_location = layout_to_space(
+            &self.space,
+            (evt.x_transformed(max_x), evt.y_transformed(max_y)).into(),
+        );
 
         // clamp to screen limits
         pointer_location = self.clamp_coords(pointer_location);
 
+        self.overview_motion(pointer_location);
         let pointer = self.pointer.clone();
         let under = self.surface_under(pointer_location);
 
@@ -924,6 +1119,7 @@ impl AnvilState<UdevData> {
             },
         );
         pointer.frame(self);
+        self.focus_follows_pointer(pointer_location);
     }
 
     fn on_tablet_tool_axis<B: InputBackend>(&mut self, evt: B::TabletToolAxisEvent) {
@@ -1057,6 +1253,23 @@ impl AnvilState<UdevData> {
     }
 
     fn on_gesture_swipe_begin<B: InputBackend>(&mut self, evt: B::GestureSwipeBeginEvent) {
+        // three or more fingers pan the board instead of going to the client
+        if evt.fingers() >= 3 {
+            let output = self
+                .space
+                .output_under(self.pointer.current_location())
+                .next()
+                .cloned();
+            if let Some(output) = output {
+                cancel_scroll(&output);
+                self.pan_gesture = Some(PanGesture {
+                    output,
+                    remainder: Point::default(),
+                });
+                return;
+            }
+        }
+
         let serial = SCOUNTER.next_serial();
         let pointer = self.pointer.clone();
         pointer.gesture_swipe_begin(
@@ -1070,6 +1283,16 @@ impl AnvilState<UdevData> {
     }
 
     fn on_gesture_swipe_update<B: InputBackend>(&mut self, evt: B::GestureSwipeUpdateEvent) {
+        if let Some(gesture) = self.pan_gesture.as_mut() {
+            // the board follows the fingers, so the viewport moves the other way
+            let delta = gesture.remainder - evt.delta();
+            let pan = delta.to_i32_round();
+            gesture.remainder = delta - pan.to_f64();
+            let output = gesture.output.clone();
+            self.pan_viewport(&output, pan);
+            return;
+        }
+
         let pointer = self.pointer.clone();
         pointer.gesture_swipe_update(
             self,
@@ -1081,6 +1304,10 @@ impl AnvilState<UdevData> {
     }
 
     fn on_gesture_swipe_end<B: InputBackend>(&mut self, evt: B::GestureSwipeEndEvent) {
+        if self.pan_gesture.take().is_some() {
+            return;
+        }
+
         let serial = SCOUNTER.next_serial();
         let pointer = self.pointer.clone();
         pointer.gesture_swipe_end(
@@ -1250,31 +1477,46 @@ impl AnvilState<UdevData> {
     }
 
     fn clamp_coords(&self, pos: Point<f64, Logical>) -> Point<f64, Logical> {
-        if self.space.outputs().next().is_none() {
-            return pos;
-        }
-
-        let (pos_x, pos_y) = pos.into();
-        let max_x = self
-            .space
-            .outputs()
-            .fold(0, |acc, o| acc + self.space.output_geometry(o).unwrap().size.w);
-        let clamped_x = pos_x.clamp(0.0, max_x as f64);
-        let max_y = self
-            .space
+        // Outputs are viewports onto the board and might not touch each other,
+        // keep the pointer on the closest one.
+        self.space
             .outputs()
-            .find(|o| {
-                let geo = self.space.output_geometry(o).unwrap();
-                geo.contains((clamped_x as i32, 0))
+            .filter_map(|o| self.space.output_geometry(o))
+            .map(|geo| {
+                let clamped_x = pos.x.clamp(geo.loc.x as f64, (geo.loc.x + geo.size.w) as f64);
+                let clamped_y = pos.y.clamp(geo.loc.y as f64, (geo.loc.y + geo.size.h) as f64);
+                Point::<f64, Logical>::from((clamped_x, clamped_y))
             })
-            .map(|o| self.space.output_geometry(o).unwrap().size.h);
+            .min_by(|a, b| {
+                let distance_a = (a.x - pos.x).powi(2) + (a.y - pos.y).powi(2);
+                let distance_b = (b.x - pos.x).powi(2) + (b.y - pos.y).powi(2);
+                distance_a.total_cmp(&distance_b)
+            })
+            .unwrap_or(pos)
+    }
+}
 
-        if let Some(max_y) = max_y {
-            let clamped_y = pos_y.clamp(0.0, max_y as f64);
-            (clamped_x, clamped_y).into()
-        } else {
-            (clamped_x, pos_y).into()
-        }
+/// Keys moving the selection of the window switcher
+fn process_switcher_key(keysym: Keysym) -> Option<KeyAction> {
+    match keysym {
+        Keysym::Left | Keysym::Up => Some(KeyAction::SwitchWindow(true)),
+        Keysym::Right | Keysym::Down => Some(KeyAction::SwitchWindow(false)),
+        Keysym::Return | Keysym::KP_Enter | Keysym::space => Some(KeyAction::SwitcherSelect),
+        Keysym::Escape => Some(KeyAction::SwitcherCancel),
+        _ => None,
+    }
+}
+
+/// Keys navigating the board overview
+fn process_overview_key(keysym: Keysym) -> Option<KeyAction> {
+    match keysym {
+        Keysym::Left => Some(KeyAction::OverviewMove(Direction::Left)),
+        Keysym::Right => Some(KeyAction::OverviewMove(Direction::Right)),
+        Keysym::Up => Some(KeyAction::OverviewMove(Direction::Up)),
+        Keysym::Down => Some(KeyAction::OverviewMove(Direction::Down)),
+        Keysym::Return | Keysym::KP_Enter | Keysym::space => Some(KeyAction::OverviewSelect),
+        Keysym::Escape => Some(KeyAction::TogglePreview),
+        _ => None,
     }
 }
 
@@ -1293,6 +1535,42 @@ enum KeyAction {
     ScaleUp,
     ScaleDown,
     TogglePreview,
+    /// Move the viewport of the current output across the board
+    Pan(Direction),
+    /// Focus the nearest window in a direction
+    Focus(Direction),
+    /// Exchange the cell of the focused window with its neighbour's
+    Swap(Direction),
+    /// Move the focused window into an empty neighbouring cell
+    Move(Direction),
+    /// Swap the focused window into the center cell
+    Promote,
+    /// Take the focused window off the board or put it back
+    ToggleFloating,
+    /// Move the focused window into a stacking class, or back into the normal one
+    Stacking(StackingClass),
+    /// Make the focused window cover its output, or return it to its cell
+    ToggleFullscreen,
+    /// Hide the focused window until it is restored
+    Minimize,
+    /// Restore the window minimized last
+    RestoreLast,
+    /// Focus the window that has wanted attention the longest
+    FocusUrgent,
+    /// Change the opacity of the focused window by the given amount
+    Opacity(f32),
+    /// Show or hide the window of the named scratchpad
+    Scratchpad(String),
+    /// Move the selection of the board overview to a neighbouring cell
+    OverviewMove(Direction),
+    /// Focus the window selected in the board overview
+    OverviewSelect,
+    /// Open the window switcher, or select the next window in it (the previous one if true)
+    SwitchWindow(bool),
+    /// Focus the window selected in the switcher
+    SwitcherSelect,
+    /// Close the switcher without changing the focus
+    SwitcherCancel,
     RotateOutput,
     ToggleTint,
     ToggleDecorations,
@@ -1303,23 +1581,31 @@ enum KeyAction {
 fn process_keyboard_shortcut(modifiers: ModifiersState, keysym: Keysym) -> Option<KeyAction> {
     // Check for keybindings from config first
     if let Some(config) = GLOBAL_CONFIG.read().unwrap().as_ref() {
-        for binding in &config.keybindings {
+        // Bindings asking for more modifiers are tried first, so Logo+Shift+Left isn't
+        // taken by a Logo+Left binding while extra modifiers still fall back to it
+        let mut bindings = config.keybindings.iter().collect::<Vec<_>>();
+        bindings.sort_by_key(|binding| std::cmp::Reverse(binding.modifiers.len()));
+        for binding in bindings {
             // Parse the key from the binding
             if let Some(binding_keysym) = crate::config::parse_keysym(&binding.key) {
                 if binding_keysym == keysym {
-                    // Check if modifiers match
-                    let mut matches = true;
-                    
-                    // Check that all required modifiers are pressed
-                    for mod_name in &binding.modifiers {
-                        if let Some(mod_state) = crate::config::parse_modifier(mod_name) {
-                            if !modifiers.contains(mod_state) {
-                                matches = false;
-                                break;
-                            }
-                        }
-                    }
-                    
+                    // Check that all required modifiers are pressed, extra ones are ignored
+                    let required = binding
+                        .modifiers
+                        .iter()
+                        .filter_map(|mod_name| crate::config::parse_modifier(mod_name))
+                        .fold(ModifiersState::default(), |mut acc, state| {
+                            acc.shift |= state.shift;
+                            acc.ctrl |= state.ctrl;
+                            acc.alt |= state.alt;
+                            acc.logo |= state.logo;
+                            acc
+                        });
+                    let matches = (!required.shift || modifiers.shift)
+                        && (!required.ctrl || modifiers.ctrl)
+                        && (!required.alt || modifiers.alt)
+                        && (!required.logo || modifiers.logo);
+
                     // If all modifiers match, return the action
                     if matches {
                         match binding.action.as_str() {
@@ -1331,6 +1617,64 @@ fn process_keyboard_shortcut(modifiers: ModifiersState, keysym: Keysym) -> Optio
                                     }
                                 }
                             },
+                            "overview" => return Some(KeyAction::TogglePreview),
+                            "focus" | "swap" | "move" => {
+                                if let Some(direction) = binding
+                                    .args
+                                    .as_ref()
+                                    .and_then(|args| args.get("direction"))
+                                    .and_then(|direction| parse_direction(direction))
+                                {
+                                    return Some(match binding.action.as_str() {
+                                        "focus" => KeyAction::Focus(direction),
+                                        "swap" => KeyAction::Swap(direction),
+                                        _ => KeyAction::Move(direction),
+                                    });
+                                }
+                            },
+                            "promote" => return Some(KeyAction::Promote),
+                            "toggle_floating" => return Some(KeyAction::ToggleFloating),
+                            "toggle_fullscreen" => return Some(KeyAction::ToggleFullscreen),
+                            "minimize" => return Some(KeyAction::Minimize),
+                            "switch_next" => return Some(KeyAction::SwitchWindow(false)),
+                            "switch_previous" => return Some(KeyAction::SwitchWindow(true)),
+                            "restore_last" => return Some(KeyAction::RestoreLast),
+                            "focus_urgent" => return Some(KeyAction::FocusUrgent),
+                            "scratchpad" => {
+                                if let Some(name) = binding.args.as_ref().and_then(|args| args.get("name")) {
+                                    return Some(KeyAction::Scratchpad(name.clone()));
+                                }
+                            },
+                            "stacking" => {
+                                if let Some(class) = binding
+                                    .args
+                                    .as_ref()
+                                    .and_then(|args| args.get("class"))
+                                    .and_then(|class| parse_stacking_class(class))
+                                {
+                                    return Some(KeyAction::Stacking(class));
+                                }
+                            },
+                            "opacity" => {
+                                if let Some(delta) = binding
+                                    .args
+                                    .as_ref()
+                                    .and_then(|args| args.get("change"))
+                                    .and_then(|change| change.parse::<f32>().ok())
+                                {
+                                    return Some(KeyAction::Opacity(delta));
+                                }
+                            },
+                            "pan" => {
+                                if let Some(direction) = binding
+                                    .args
+                                    .as_ref()
+                                    .and_then(|args| args.get("direction"))
+                                    .and_then(|direction| parse_direction(direction))
+                                {
+                                    return Some(KeyAction::Pan(direction));
+                                }
+                            },
                             _ => {}
                         }
                     }
@@ -1367,6 +1711,48 @@ fn process_keyboard_shortcut(modifiers: ModifiersState, keysym: Keysym) -> Optio
         Some(KeyAction::ToggleTint)
     } else if modifiers.logo && modifiers.shift && keysym == Keysym::D {
         Some(KeyAction::ToggleDecorations)
+    } else if modifiers.logo && modifiers.shift && keysym == Keysym::F {
+        Some(KeyAction::ToggleFloating)
+    } else if modifiers.logo && modifiers.shift && keysym == Keysym::A {
+        Some(KeyAction::Stacking(StackingClass::Above))
+    } else if modifiers.logo && modifiers.shift && keysym == Keysym::B {
+        Some(KeyAction::Stacking(StackingClass::Below))
+    } else if modifiers.alt && !modifiers.logo && (keysym == Keysym::Tab || keysym == Keysym::ISO_Left_Tab) {
+        Some(KeyAction::SwitchWindow(modifiers.shift))
+    } else if modifiers.logo && modifiers.shift && keysym == Keysym::N {
+        Some(KeyAction::RestoreLast)
+    } else if modifiers.logo && keysym == Keysym::u {
+        Some(KeyAction::FocusUrgent)
+    } else if modifiers.logo && keysym == Keysym::minus {
+        Some(KeyAction::Opacity(-0.1))
+    } else if modifiers.logo && keysym == Keysym::equal {
+        Some(KeyAction::Opacity(0.1))
+    } else if modifiers.logo && keysym == Keysym::n {
+        Some(KeyAction::Minimize)
+    } else if modifiers.logo && !modifiers.ctrl && !modifiers.shift && keysym == Keysym::Left {
+        Some(KeyAction::Focus(Direction::Left))
+    } else if modifiers.logo && !modifiers.ctrl && !modifiers.shift && keysym == Keysym::Right {
+        Some(KeyAction::Focus(Direction::Right))
+    } else if modifiers.logo && !modifiers.ctrl && !modifiers.shift && keysym == Keysym::Up {
+        Some(KeyAction::Focus(Direction::Up))
+    } else if modifiers.logo && !modifiers.ctrl && !modifiers.shift && keysym == Keysym::Down {
+        Some(KeyAction::Focus(Direction::Down))
+    } else if modifiers.logo && modifiers.shift && keysym == Keysym::Left {
+        Some(KeyAction::Swap(Direction::Left))
+    } else if modifiers.logo && modifiers.shift && keysym == Keysym::Right {
+        Some(KeyAction::Swap(Direction::Right))
+    } else if modifiers.logo && modifiers.shift && keysym == Keysym::Up {
+        Some(KeyAction::Swap(Direction::Up))
+    } else if modifiers.logo && modifiers.shift && keysym == Keysym::Down {
+        Some(KeyAction::Swap(Direction::Down))
+    } else if modifiers.logo && modifiers.ctrl && keysym == Keysym::Left {
+        Some(KeyAction::Pan(Direction::Left))
+    } else if modifiers.logo && modifiers.ctrl && keysym == Keysym::Right {
+        Some(KeyAction::Pan(Direction::Right))
+    } else if modifiers.logo && modifiers.ctrl && keysym == Keysym::Up {
+        Some(KeyAction::Pan(Direction::Up))
+    } else if modifiers.logo && modifiers.ctrl && keysym == Keysym::Down {
+        Some(KeyAction::Pan(Direction::Down))
     } else {
         None
     }
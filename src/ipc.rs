@@ -0,0 +1,189 @@
+//! Socket for querying the compositor from scripts and status bars.
+//!
+//! The socket lives next to the wayland socket as `$XDG_RUNTIME_DIR/anvil-<wayland display>.sock`,
+//! programs started by the compositor find it in `ANVIL_SOCKET`. Every request is a single
+//! line and is answered with a single line of JSON:
+//!
+//! - `urgent`: the windows wanting attention, the one waiting longest first
+
+use std::{
+    fs,
+    io::{self, Read, Write},
+    os::unix::net::{UnixListener, UnixStream},
+    path::{Path, PathBuf},
+};
+
+use serde_json::{json, Value};
+use smithay::reexports::calloop::{generic::Generic, Interest, LoopHandle, Mode, PostAction};
+use tracing::{info, warn};
+
+use crate::{
+    shell::window_identity,
+    state::{AnvilState, Backend},
+};
+
+/// Longest request a client may send, clients sending more without a newline are dropped
+const MAX_REQUEST_LEN: usize = 4096;
+/// Most bytes of answers queued for a client not reading them before it is dropped
+const MAX_RESPONSE_LEN: usize = 1024 * 1024;
+
+/// Path of the IPC socket, the socket file is removed when this is dropped
+#[derive(Debug)]
+pub struct IpcSocket {
+    path: PathBuf,
+}
+
+impl IpcSocket {
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for IpcSocket {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+/// Starts listening on the IPC socket for the wayland socket of the given name.
+///
+/// Returns none if the socket couldn't be created.
+pub fn listen<BackendData: Backend + 'static>(
+    handle: &LoopHandle<'static, AnvilState<BackendData>>,
+    socket_name: &str,
+) -> Option<IpcSocket> {
+    let path =
+        PathBuf::from(std::env::var_os("XDG_RUNTIME_DIR")?).join(format!("anvil-{}.sock", socket_name));
+    // left behind by a compositor that didn't exit cleanly
+    let _ = fs::remove_file(&path);
+    let listener = match UnixListener::bind(&path).and_then(|listener| {
+        listener.set_nonblocking(true)?;
+        Ok(listener)
+    }) {
+        Ok(listener) => listener,
+        Err(err) => {
+            warn!(?path, "Failed to create IPC socket: {}", err);
+            return None;
+        }
+    };
+
+    let client_handle = handle.clone();
+    let ret = handle.insert_source(
+        Generic::new(listener, Interest::READ, Mode::Level),
+        move |_, listener, _| {
+            // Safety: we don't drop the listener
+            let listener = unsafe { listener.get_mut() };
+            while let Ok((stream, _)) = listener.accept() {
+                accept_client(&client_handle, stream);
+            }
+            Ok(PostAction::Continue)
+        },
+    );
+    if let Err(err) = ret {
+        warn!("Failed to insert the IPC socket into the event loop: {}", err);
+        return None;
+    }
+    info!(?path, "Listening on IPC socket");
+    Some(IpcSocket { path })
+}
+
+fn accept_client<BackendData: Backend + 'static>(
+    handle: &LoopHandle<'static, AnvilState<BackendData>>,
+    stream: UnixStream,
+) {
+    if let Err(err) = stream.set_nonblocking(true) {
+        warn!("Failed to set up IPC client: {}", err);
+        return;
+    }
+
+    let mut client = Client::default();
+    let ret = handle.insert_source(
+        Generic::new(stream, Interest::BOTH, Mode::Edge),
+        move |_, stream, state| {
+            // Safety: we don't drop the stream
+            let stream = unsafe { stream.get_mut() };
+            if client.dispatch(stream, state).is_ok() {
+                Ok(PostAction::Continue)
+            } else {
+                Ok(PostAction::Remove)
+            }
+        },
+    );
+    if let Err(err) = ret {
+        warn!("Failed to insert IPC client into the event loop: {}", err);
+    }
+}
+
+/// Buffers of a connected IPC client, the stream never blocks the event loop
+#[derive(Debug, Default)]
+struct Client {
+    /// Bytes read since the last complete request
+    request: Vec<u8>,
+    /// Answers the client hasn't read yet
+    response: Vec<u8>,
+}
+
+impl Client {
+    /// Reads and answers all complete requests, then writes as much of the answers as the
+    /// socket takes. Errors if the client is gone or misbehaving and should be dropped.
+    fn dispatch<BackendData: Backend>(
+        &mut self,
+        stream: &mut UnixStream,
+        state: &AnvilState<BackendData>,
+    ) -> io::Result<()> {
+        let mut buffer = [0; 1024];
+        loop {
+            match stream.read(&mut buffer) {
+                Ok(0) => return Err(io::ErrorKind::UnexpectedEof.into()),
+                Ok(read) => self.request.extend_from_slice(&buffer[..read]),
+                Err(err) if err.kind() == io::ErrorKind::WouldBlock => break,
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                Err(err) => return Err(err),
+            }
+
+            while let Some(end) = self.request.iter().position(|byte| *byte == b'\n') {
+                let line = self.request.drain(..=end).collect::<Vec<_>>();
+                let response = state.ipc_request(String::from_utf8_lossy(&line).trim());
+                writeln!(self.response, "{}", response)?;
+            }
+            if self.request.len() > MAX_REQUEST_LEN || self.response.len() > MAX_RESPONSE_LEN {
+                return Err(io::ErrorKind::OutOfMemory.into());
+            }
+        }
+
+        while !self.response.is_empty() {
+            match stream.write(&self.response) {
+                Ok(0) => return Err(io::ErrorKind::WriteZero.into()),
+                Ok(written) => {
+                    self.response.drain(..written);
+                }
+                Err(err) if err.kind() == io::ErrorKind::WouldBlock => break,
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                Err(err) => return Err(err),
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<BackendData: Backend> AnvilState<BackendData> {
+    /// Answers a request read from the IPC socket
+    fn ipc_request(&self, request: &str) -> Value {
+        match request {
+            "urgent" => self
+                .urgent_windows()
+                .iter()
+                .map(|window| {
+                    let (app_id, title) = window_identity(window);
+                    json!({
+                        "app_id": app_id,
+                        "title": title,
+                        "minimized": self.minimized.contains(window),
+                        "urgent_ms": window.urgent_since().map(|since| since.elapsed().as_millis() as u64),
+                    })
+                })
+                .collect(),
+            request => json!({ "error": format!("unknown request: {}", request) }),
+        }
+    }
+}
@@ -8,9 +8,11 @@
 
 #[cfg(any(feature = "udev", feature = "xwayland"))]
 pub mod cursor;
+pub mod config;
 pub mod drawing;
 pub mod focus;
 pub mod input_handler;
+pub mod ipc;
 pub mod render;
 pub mod shell;
 pub mod state;
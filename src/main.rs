@@ -12,6 +12,7 @@ mod cursor;
 mod drawing;
 mod focus;
 mod input_handler;
+mod ipc;
 mod render;
 mod shell;
 mod state;
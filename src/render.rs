@@ -2,12 +2,13 @@ use smithay::{
     backend::renderer::{
         damage::{Error as OutputDamageTrackerError, OutputDamageTracker, RenderOutputResult},
         element::{
+            solid::SolidColorRenderElement,
             surface::WaylandSurfaceRenderElement,
             utils::{
                 ConstrainAlign, ConstrainScaleBehavior, CropRenderElement, RelocateRenderElement,
                 RescaleRenderElement,
             },
-            AsRenderElements, RenderElement, Wrap,
+            AsRenderElements, Kind, RenderElement, Wrap,
         },
         Color32F, ImportAll, ImportMem, Renderer,
     },
@@ -15,14 +16,16 @@ use smithay::{
         constrain_space_element, ConstrainBehavior, ConstrainReference, Space, SpaceRenderElements,
     },
     output::Output,
-    utils::{Point, Rectangle, Size},
 };
 
 #[cfg(feature = "debug")]
 use crate::drawing::FpsElement;
 use crate::{
-    drawing::{PointerRenderElement, CLEAR_COLOR, CLEAR_COLOR_FULLSCREEN},
-    shell::{FullscreenSurface, WindowElement, WindowRenderElement},
+    drawing::{EffectsRenderer, PointerRenderElement, CLEAR_COLOR, CLEAR_COLOR_FULLSCREEN},
+    shell::{
+        board_windows, FullscreenSurface, Overview, OverviewLayout, Switcher, WindowElement,
+        WindowRenderElement,
+    },
 };
 
 smithay::backend::renderer::element::render_elements! {
@@ -51,11 +54,12 @@ impl<R: Renderer> std::fmt::Debug for CustomRenderElements<R> {
 }
 
 smithay::backend::renderer::element::render_elements! {
-    pub OutputRenderElements<R, E> where R: ImportAll + ImportMem;
+    pub OutputRenderElements<R, E> where R: ImportAll + ImportMem + EffectsRenderer;
     Space=SpaceRenderElements<R, E>,
     Window=Wrap<E>,
     Custom=CustomRenderElements<R>,
     Preview=CropRenderElement<RelocateRenderElement<RescaleRenderElement<WindowRenderElement<R>>>>,
+    Solid=SolidColorRenderElement,
 }
 
 impl<R: Renderer + ImportAll + ImportMem, E: RenderElement<R> + std::fmt::Debug> std::fmt::Debug
@@ -67,72 +71,127 @@ impl<R: Renderer + ImportAll + ImportMem, E: RenderElement<R> + std::fmt::Debug>
             Self::Window(arg0) => f.debug_tuple("Window").field(arg0).finish(),
             Self::Custom(arg0) => f.debug_tuple("Custom").field(arg0).finish(),
             Self::Preview(arg0) => f.debug_tuple("Preview").field(arg0).finish(),
+            Self::Solid(arg0) => f.debug_tuple("Solid").field(arg0).finish(),
             Self::_GenericCatcher(arg0) => f.debug_tuple("_GenericCatcher").field(arg0).finish(),
         }
     }
 }
 
-pub fn space_preview_elements<'a, R, C>(
-    renderer: &'a mut R,
-    space: &'a Space<WindowElement>,
-    output: &'a Output,
-) -> impl Iterator<Item = C> + 'a
+/// Whole board scaled to fit the output, one thumbnail per occupied cell,
+/// with the minimized windows in a row below it
+pub fn overview_elements<R>(
+    renderer: &mut R,
+    space: &Space<WindowElement>,
+    output: &Output,
+    overview: &Overview,
+) -> Vec<OutputRenderElements<R, WindowRenderElement<R>>>
 where
-    R: Renderer + ImportAll + ImportMem,
+    R: Renderer + ImportAll + ImportMem + EffectsRenderer,
     R::TextureId: Clone + 'static,
-    C: From<CropRenderElement<RelocateRenderElement<RescaleRenderElement<WindowRenderElement<R>>>>> + 'a,
 {
+    let Some(layout) = OverviewLayout::for_output(space, output, overview.minimized.len()) else {
+        return Vec::new();
+    };
     let constrain_behavior = ConstrainBehavior {
         reference: ConstrainReference::BoundingBox,
         behavior: ConstrainScaleBehavior::Fit,
         align: ConstrainAlign::CENTER,
     };
-
-    let preview_padding = 10;
-
-    let elements_on_space = space.elements_for_output(output).count();
     let output_scale = output.current_scale().fractional_scale();
-    let output_transform = output.current_transform();
-    let output_size = output
-        .current_mode()
-        .map(|mode| {
-            output_transform
-                .transform_size(mode.size)
-                .to_f64()
-                .to_logical(output_scale)
-        })
-        .unwrap_or_default();
-
-    let max_elements_per_row = 4;
-    let elements_per_row = usize::min(elements_on_space, max_elements_per_row);
-    let rows = f64::ceil(elements_on_space as f64 / elements_per_row as f64);
-
-    let preview_size = Size::from((
-        f64::round(output_size.w / elements_per_row as f64) as i32 - preview_padding * 2,
-        f64::round(output_size.h / rows) as i32 - preview_padding * 2,
-    ));
-
-    space
-        .elements_for_output(output)
-        .enumerate()
-        .flat_map(move |(element_index, window)| {
-            let column = element_index % elements_per_row;
-            let row = element_index / elements_per_row;
-            let preview_location = Point::from((
-                preview_padding + (preview_padding + preview_size.w) * column as i32,
-                preview_padding + (preview_padding + preview_size.h) * row as i32,
-            ));
-            let constrain = Rectangle::new(preview_location, preview_size);
-            constrain_space_element(
+
+    let mut thumbnails: Vec<OutputRenderElements<R, WindowRenderElement<R>>> = Vec::new();
+    let mut backgrounds = Vec::new();
+    for index in layout.cells() {
+        let rect = layout.cell_rect(index);
+        if let Some(window) = board_windows(space, output).find(|w| w.board_cell() == Some(index)) {
+            thumbnails.extend(constrain_space_element(
                 renderer,
                 window,
-                preview_location,
+                rect.loc,
                 1.0,
                 output_scale,
-                constrain,
+                rect,
                 constrain_behavior,
-            )
-        })
+            ));
+        }
+        let buffer = overview.cell_background(output, index, rect.size);
+        backgrounds.push(OutputRenderElements::Solid(SolidColorRenderElement::from_buffer(
+            &buffer,
+            rect.loc.to_physical_precise_round(output_scale),
+            output_scale,
+            1.0,
+            Kind::Unspecified,
+        )));
+    }
+    for (slot, window) in overview.minimized.iter().enumerate() {
+        let rect = layout.slot_rect(slot);
+        thumbnails.extend(constrain_space_element(
+            renderer,
+            window,
+            rect.loc,
+            1.0,
+            output_scale,
+            rect,
+            constrain_behavior,
+        ));
+        let buffer = overview.slot_background(output, slot, rect.size);
+        backgrounds.push(OutputRenderElements::Solid(SolidColorRenderElement::from_buffer(
+            &buffer,
+            rect.loc.to_physical_precise_round(output_scale),
+            output_scale,
+            1.0,
+            Kind::Unspecified,
+        )));
+    }
+
+    thumbnails.into_iter().chain(backgrounds).collect()
+}
+
+/// Thumbnails of the windows in the switcher, shown on top of the output
+pub fn switcher_elements<R>(
+    renderer: &mut R,
+    space: &Space<WindowElement>,
+    output: &Output,
+    switcher: &Switcher,
+) -> Vec<OutputRenderElements<R, WindowRenderElement<R>>>
+where
+    R: Renderer + ImportAll + ImportMem + EffectsRenderer,
+    R::TextureId: Clone + 'static,
+{
+    let Some(output_size) = space.output_geometry(output).map(|geometry| geometry.size) else {
+        return Vec::new();
+    };
+    let constrain_behavior = ConstrainBehavior {
+        reference: ConstrainReference::BoundingBox,
+        behavior: ConstrainScaleBehavior::Fit,
+        align: ConstrainAlign::CENTER,
+    };
+    let output_scale = output.current_scale().fractional_scale();
+
+    let mut thumbnails: Vec<OutputRenderElements<R, WindowRenderElement<R>>> = Vec::new();
+    let mut backgrounds = Vec::new();
+    for (index, window) in switcher.windows.iter().enumerate() {
+        let rect = switcher.thumbnail_rect(output_size, index);
+        thumbnails.extend(constrain_space_element(
+            renderer,
+            window,
+            rect.loc,
+            1.0,
+            output_scale,
+            rect,
+            constrain_behavior,
+        ));
+        let (area, buffer) = switcher.slot_background(output_size, index);
+        backgrounds.push(OutputRenderElements::Solid(SolidColorRenderElement::from_buffer(
+            &buffer,
+            area.loc.to_physical_precise_round(output_scale),
+            output_scale,
+            1.0,
+            Kind::Unspecified,
+        )));
+    }
+
+    thumbnails.into_iter().chain(backgrounds).collect()
 }
 
 #[profiling::function]
@@ -141,13 +200,25 @@ pub fn output_elements<R>(
     space: &Space<WindowElement>,
     custom_elements: impl IntoIterator<Item = CustomRenderElements<R>>,
     renderer: &mut R,
-    show_window_preview: bool,
+    overview: Option<&Overview>,
+    switcher: Option<&Switcher>,
 ) -> (Vec<OutputRenderElements<R, WindowRenderElement<R>>>, Color32F)
 where
-    R: Renderer + ImportAll + ImportMem,
+    R: Renderer + ImportAll + ImportMem + EffectsRenderer,
     R::TextureId: Clone + 'static,
 {
-    if let Some(window) = output
+    let mut elements = custom_elements
+        .into_iter()
+        .map(OutputRenderElements::from)
+        .collect::<Vec<_>>();
+    if let Some(switcher) = switcher.filter(|switcher| switcher.output == *output) {
+        elements.extend(switcher_elements(renderer, space, output, switcher));
+    }
+
+    if let Some(overview) = overview {
+        elements.extend(overview_elements(renderer, space, output, overview));
+        (elements, CLEAR_COLOR)
+    } else if let Some(window) = output
         .user_data()
         .get::<FullscreenSurface>()
         .and_then(|f| f.get())
@@ -156,26 +227,13 @@ where
         let window_render_elements: Vec<WindowRenderElement<R>> =
             AsRenderElements::<R>::render_elements(&window, renderer, (0, 0).into(), scale, 1.0);
 
-        let elements = custom_elements
-            .into_iter()
-            .map(OutputRenderElements::from)
-            .chain(
-                window_render_elements
-                    .into_iter()
-                    .map(|e| OutputRenderElements::Window(Wrap::from(e))),
-            )
-            .collect::<Vec<_>>();
+        elements.extend(
+            window_render_elements
+                .into_iter()
+                .map(|e| OutputRenderElements::Window(Wrap::from(e))),
+        );
         (elements, CLEAR_COLOR_FULLSCREEN)
     } else {
-        let mut output_render_elements = custom_elements
-            .into_iter()
-            .map(OutputRenderElements::from)
-            .collect::<Vec<_>>();
-
-        if show_window_preview && space.elements_for_output(output).count() > 0 {
-            output_render_elements.extend(space_preview_elements(renderer, space, output));
-        }
-
         let space_elements = smithay::desktop::space::space_render_elements::<_, WindowElement, _>(
             renderer,
             [space],
@@ -183,9 +241,9 @@ where
             1.0,
         )
         .expect("output without mode?");
-        output_render_elements.extend(space_elements.into_iter().map(OutputRenderElements::Space));
+        elements.extend(space_elements.into_iter().map(OutputRenderElements::Space));
 
-        (output_render_elements, CLEAR_COLOR)
+        (elements, CLEAR_COLOR)
     }
 }
 
@@ -198,13 +256,14 @@ pub fn render_output<'a, 'd, R>(
     framebuffer: &'a mut R::Framebuffer<'_>,
     damage_tracker: &'d mut OutputDamageTracker,
     age: usize,
-    show_window_preview: bool,
+    overview: Option<&Overview>,
+    switcher: Option<&Switcher>,
 ) -> Result<RenderOutputResult<'d>, OutputDamageTrackerError<R::Error>>
 where
-    R: Renderer + ImportAll + ImportMem,
+    R: Renderer + ImportAll + ImportMem + EffectsRenderer,
     R::TextureId: Clone + 'static,
 {
     let (elements, clear_color) =
-        output_elements(output, space, custom_elements, renderer, show_window_preview);
+        output_elements(output, space, custom_elements, renderer, overview, switcher);
     damage_tracker.render_output(renderer, framebuffer, age, &elements, clear_color)
 }
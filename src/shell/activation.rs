@@ -0,0 +1,70 @@
+use smithay::{
+    input::Seat,
+    reexports::wayland_server::Resource,
+    wayland::{seat::WaylandFocus, xdg_activation::XdgActivationTokenData},
+};
+
+use crate::{
+    config::FocusStealing,
+    state::{AnvilState, Backend},
+};
+
+use super::WindowElement;
+
+/// Seconds an activation token may take the focus after it was created
+const TOKEN_TIMEOUT: u64 = 10;
+
+/// Whether the token was requested with the serial of an input event the
+/// client received while it had keyboard focus
+#[derive(Debug)]
+struct TokenSerialValid(bool);
+
+impl<BackendData: Backend> AnvilState<BackendData> {
+    /// Records whether the serial the token was requested with is recent.
+    ///
+    /// Every token is accepted, an activation request with a token that may not
+    /// take the focus still marks the window urgent.
+    pub fn validate_activation_token(&self, data: &XdgActivationTokenData) {
+        let valid = data.serial.as_ref().is_some_and(|(serial, seat)| {
+            let keyboard = self.seat.get_keyboard().unwrap();
+            Seat::from_resource(seat) == Some(self.seat.clone())
+                && keyboard
+                    .last_enter()
+                    .is_some_and(|last_enter| serial.is_no_older_than(&last_enter))
+        });
+        data.user_data.insert_if_missing(|| TokenSerialValid(valid));
+    }
+
+    /// Whether an activation request for the window may take the focus.
+    ///
+    /// An `activation` window rule overrides the `focus.stealing_prevention` policy.
+    pub fn activation_granted(&self, window: &WindowElement, data: &XdgActivationTokenData) -> bool {
+        if let Some(granted) = window.rule_properties().activation {
+            return granted;
+        }
+
+        let recent = data
+            .user_data
+            .get::<TokenSerialValid>()
+            .is_some_and(|valid| valid.0)
+            && data.timestamp.elapsed().as_secs() < TOKEN_TIMEOUT;
+        match crate::config::focus().stealing_prevention {
+            FocusStealing::Off => true,
+            FocusStealing::Smart => recent,
+            FocusStealing::Strict => recent && self.token_from_focused_client(data),
+        }
+    }
+
+    /// Whether the token was created by the client holding the keyboard focus
+    fn token_from_focused_client(&self, data: &XdgActivationTokenData) -> bool {
+        let focused_client = self
+            .seat
+            .get_keyboard()
+            .and_then(|keyboard| keyboard.current_focus())
+            .and_then(|focus| focus.wl_surface().and_then(|surface| surface.client()));
+        match (focused_client, data.client_id.as_ref()) {
+            (Some(client), Some(id)) => client.id() == *id,
+            _ => false,
+        }
+    }
+}
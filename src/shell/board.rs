@@ -0,0 +1,430 @@
+use std::cell::{Cell, RefCell};
+
+use smithay::{
+    desktop::{layer_map_for_output, space::SpaceElement, Space, WindowSurface},
+    output::Output,
+    reexports::wayland_protocols::xdg::shell::server::xdg_toplevel,
+    utils::{Logical, Point, Rectangle, Size},
+    wayland::{
+        compositor::with_states,
+        shell::xdg::{SurfaceCachedState, ToplevelSurface},
+    },
+};
+
+use super::{map_with_transients, output_home, ssd::HEADER_BAR_HEIGHT, WindowElement};
+
+/// Identifies an output across hotplugs by its connector and the monitor's EDID
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OutputIdentity {
+    pub connector: String,
+    pub make: String,
+    pub model: String,
+}
+
+impl OutputIdentity {
+    pub fn of(output: &Output) -> Self {
+        let properties = output.physical_properties();
+        OutputIdentity {
+            connector: output.name(),
+            make: properties.make,
+            model: properties.model,
+        }
+    }
+
+    /// Whether the output is this monitor on this connector
+    pub fn is(&self, output: &Output) -> bool {
+        let properties = output.physical_properties();
+        output.name() == self.connector && properties.make == self.make && properties.model == self.model
+    }
+
+    /// Mapped output matching this identity.
+    ///
+    /// The same monitor on the same connector is preferred, the same monitor
+    /// plugged into another connector (e.g. a different dock port) is accepted too.
+    pub fn find(&self, space: &Space<WindowElement>) -> Option<Output> {
+        let same_monitor = |output: &Output| {
+            let properties = output.physical_properties();
+            properties.make == self.make && properties.model == self.model
+        };
+        space
+            .outputs()
+            .find(|output| self.is(output))
+            .or_else(|| {
+                // without EDID information all monitors look the same
+                if self.make.is_empty() && self.model.is_empty() {
+                    return None;
+                }
+                space.outputs().find(|output| same_monitor(output))
+            })
+            .cloned()
+    }
+}
+
+/// Board and cell a window occupies
+#[derive(Debug, Default)]
+pub struct BoardCell {
+    /// Output owning the board
+    output: RefCell<Option<OutputIdentity>>,
+    /// Spiral index of the cell
+    index: Cell<Option<usize>>,
+    /// Board and cell the window had to leave because its output went away
+    origin: RefCell<Option<(OutputIdentity, usize)>>,
+}
+
+impl WindowElement {
+    fn board_data(&self) -> &BoardCell {
+        self.user_data().insert_if_missing(BoardCell::default);
+        self.user_data().get::<BoardCell>().unwrap()
+    }
+
+    pub fn board_cell(&self) -> Option<usize> {
+        self.user_data()
+            .get::<BoardCell>()
+            .and_then(|cell| cell.index.get())
+    }
+
+    pub fn set_board_cell(&self, index: Option<usize>) {
+        self.board_data().index.set(index);
+    }
+
+    /// Monitor whose board the window is on, it may be unplugged
+    pub fn board_identity(&self) -> Option<OutputIdentity> {
+        self.user_data()
+            .get::<BoardCell>()
+            .and_then(|cell| cell.output.borrow().clone())
+    }
+
+    /// Output whose board the window is on, none while its monitor is unplugged
+    /// or another monitor took over its connector
+    pub fn board_output(&self, space: &Space<WindowElement>) -> Option<Output> {
+        let identity = self.board_identity()?;
+        space.outputs().find(|output| identity.is(output)).cloned()
+    }
+
+    pub fn set_board_output(&self, output: Option<&Output>) {
+        *self.board_data().output.borrow_mut() = output.map(OutputIdentity::of);
+    }
+
+    /// Board and cell to return to once the output comes back
+    pub fn board_origin(&self) -> Option<(OutputIdentity, usize)> {
+        self.user_data()
+            .get::<BoardCell>()
+            .and_then(|cell| cell.origin.borrow().clone())
+    }
+
+    /// Remembers the current board and cell as the place to return to,
+    /// unless the window already has one from an earlier hotplug
+    pub fn remember_board_origin(&self) {
+        let data = self.board_data();
+        let current = data.output.borrow().clone().zip(data.index.get());
+        let mut origin = data.origin.borrow_mut();
+        if origin.is_none() {
+            *origin = current;
+        }
+    }
+
+    pub fn clear_board_origin(&self) {
+        *self.board_data().origin.borrow_mut() = None;
+    }
+
+    /// Whether the window occupies a cell on the board of the given output
+    pub fn is_on_board(&self, output: &Output) -> bool {
+        self.board_cell().is_some()
+            && self.user_data().get::<BoardCell>().is_some_and(|cell| {
+                cell.output
+                    .borrow()
+                    .as_ref()
+                    .is_some_and(|identity| identity.is(output))
+            })
+    }
+}
+
+/// Offset of a cell from the center of the board, in cells.
+///
+/// Index 0 is the center, the following indices walk the rings around it
+/// clockwise, each ring starting with the cell to the right of the center.
+pub fn cell_offset(index: usize) -> Point<i32, Logical> {
+    if index == 0 {
+        return Point::from((0, 0));
+    }
+
+    let mut ring = 1;
+    while (2 * ring + 1) * (2 * ring + 1) <= index {
+        ring += 1;
+    }
+    let position = (index - (2 * ring - 1) * (2 * ring - 1)) as i32;
+    let ring = ring as i32;
+    let side = ring * 2;
+
+    let (x, y) = if position < ring {
+        // right, downwards
+        (ring, position)
+    } else if position < ring + side {
+        // bottom, leftwards
+        (ring - (position - ring), ring)
+    } else if position < ring + side * 2 {
+        // left, upwards
+        (-ring, ring - (position - ring - side))
+    } else if position < ring + side * 3 {
+        // top, rightwards
+        (-ring + (position - ring - side * 2), -ring)
+    } else {
+        // right, downwards back to the start
+        (ring, -ring + (position - ring - side * 3))
+    };
+    Point::from((x, y))
+}
+
+/// Spiral index of the cell at the given offset from the center, inverse of [`cell_offset`]
+pub fn cell_index(offset: Point<i32, Logical>) -> usize {
+    let (x, y) = (offset.x, offset.y);
+    let ring = x.abs().max(y.abs());
+    if ring == 0 {
+        return 0;
+    }
+    let side = ring * 2;
+
+    let position = if x == ring && (0..ring).contains(&y) {
+        y
+    } else if y == ring && x > -ring {
+        ring + (ring - x)
+    } else if x == -ring && y > -ring {
+        ring + side + (ring - y)
+    } else if y == -ring && x < ring {
+        ring + side * 2 + (x + ring)
+    } else {
+        ring + side * 3 + (y + ring)
+    };
+    ((2 * ring - 1) * (2 * ring - 1) + position) as usize
+}
+
+/// Windows occupying a cell on the board of the given output
+pub fn board_windows<'a>(
+    space: &'a Space<WindowElement>,
+    output: &'a Output,
+) -> impl Iterator<Item = &'a WindowElement> + Clone + 'a {
+    space.elements().filter(move |window| window.is_on_board(output))
+}
+
+/// Cells of the output's board kept free for minimized windows
+#[derive(Debug, Default)]
+struct ReservedCells(RefCell<Vec<usize>>);
+
+fn reserved_cells(output: &Output) -> &ReservedCells {
+    output.user_data().insert_if_missing(ReservedCells::default);
+    output.user_data().get::<ReservedCells>().unwrap()
+}
+
+pub fn reserve_cell(output: &Output, index: usize) {
+    let mut cells = reserved_cells(output).0.borrow_mut();
+    if !cells.contains(&index) {
+        cells.push(index);
+    }
+}
+
+pub fn unreserve_cell(output: &Output, index: usize) {
+    reserved_cells(output)
+        .0
+        .borrow_mut()
+        .retain(|cell| *cell != index);
+}
+
+pub fn is_cell_reserved(output: &Output, index: usize) -> bool {
+    reserved_cells(output).0.borrow().contains(&index)
+}
+
+/// Index of the cell following the outermost occupied or reserved one
+pub fn next_cell(space: &Space<WindowElement>, output: &Output) -> usize {
+    let reserved = reserved_cells(output).0.borrow().iter().max().copied();
+    board_windows(space, output)
+        .filter_map(WindowElement::board_cell)
+        .chain(reserved)
+        .max()
+        .map_or(0, |index| index + 1)
+}
+
+/// Number of cells per row and column needed to hold the given windows
+pub fn grid_dimension<'a>(windows: impl Iterator<Item = &'a WindowElement>) -> i32 {
+    let count = windows
+        .filter_map(WindowElement::board_cell)
+        .max()
+        .map_or(0, |index| index + 1);
+    (f64::sqrt(count as f64).ceil() as i32).max(3)
+}
+
+/// Output under the given location, or any output if there is none
+pub fn output_at(space: &Space<WindowElement>, location: Point<f64, Logical>) -> Option<Output> {
+    space
+        .output_under(location)
+        .next()
+        .or_else(|| space.outputs().next())
+        .cloned()
+}
+
+/// Mapped output with the given name
+pub fn output_by_name(space: &Space<WindowElement>, name: &str) -> Option<Output> {
+    space.outputs().find(|output| output.name() == name).cloned()
+}
+
+/// Usable area of the output, its board is centered on it.
+///
+/// The board stays anchored to the home position of the output, panning the
+/// output only changes which part of the board is visible.
+pub fn board_geometry(space: &Space<WindowElement>, output: &Output) -> Rectangle<i32, Logical> {
+    output_home(space, output)
+        .map(|home| {
+            let map = layer_map_for_output(output);
+            let zone = map.non_exclusive_zone();
+            Rectangle::new(home + zone.loc, zone.size)
+        })
+        .unwrap_or_else(|| Rectangle::from_size((800, 800).into()))
+}
+
+pub fn cell_geometry(
+    board: Rectangle<i32, Logical>,
+    dimension: i32,
+    index: usize,
+) -> Rectangle<i32, Logical> {
+    let cell_size = Size::from((board.size.w / dimension, board.size.h / dimension));
+    let center = board.loc + Point::from((board.size.w / 2, board.size.h / 2));
+    let offset = cell_offset(index);
+    let cell_center = center + Point::from((offset.x * cell_size.w, offset.y * cell_size.h));
+    Rectangle::new(
+        cell_center - Point::from((cell_size.w / 2, cell_size.h / 2)),
+        cell_size,
+    )
+}
+
+/// Maps the window into the given cell.
+///
+/// With tiled sizing the window is configured to fill the cell as far as its
+/// size constraints allow, otherwise it keeps its size and is centered in the cell.
+pub fn place_in_cell(
+    space: &mut Space<WindowElement>,
+    window: &WindowElement,
+    cell: Rectangle<i32, Logical>,
+    tiled_sizing: bool,
+    activate: bool,
+) {
+    // leave room for the border inside the cell
+    let border = window.border_width();
+    let cell = Rectangle::new(
+        cell.loc + Point::from((border, border)),
+        cell.size - Size::from((border * 2, border * 2)),
+    );
+    let size = if tiled_sizing {
+        configure_tiled(window, cell)
+    } else {
+        window.bbox().size
+    };
+    let location = cell.loc + Point::from(((cell.size.w - size.w) / 2, (cell.size.h - size.h) / 2));
+    let offset = if tiled_sizing {
+        window.geometry().loc
+    } else {
+        Point::default()
+    };
+
+    map_with_transients(space, window, location - offset, activate);
+}
+
+/// Re-maps all windows on the board of the output, e.g. after the grid dimension changed
+pub fn arrange_board(space: &mut Space<WindowElement>, output: &Output, tiled_sizing: bool) {
+    let board = board_geometry(space, output);
+    let dimension = grid_dimension(board_windows(space, output));
+    let windows = board_windows(space, output)
+        .filter_map(|window| window.board_cell().map(|index| (window.clone(), index)))
+        .collect::<Vec<_>>();
+    for (window, index) in windows {
+        place_in_cell(
+            space,
+            &window,
+            cell_geometry(board, dimension, index),
+            tiled_sizing,
+            false,
+        );
+    }
+}
+
+/// Sends the cell size to the client and marks the window as tiled on all edges.
+///
+/// Returns the size the window was configured with, including decorations.
+fn configure_tiled(window: &WindowElement, cell: Rectangle<i32, Logical>) -> Size<i32, Logical> {
+    let header = if window.decoration_state().is_ssd {
+        HEADER_BAR_HEIGHT
+    } else {
+        0
+    };
+    let mut size = cell.size;
+    size.h -= header;
+
+    let size = match window.0.underlying_surface() {
+        WindowSurface::Wayland(toplevel) => {
+            let (min_size, max_size) = toplevel_size_constraints(toplevel);
+            let size = constrain_size(size, min_size, max_size);
+            toplevel.with_pending_state(|state| {
+                state.size = Some(size);
+                state.states.set(xdg_toplevel::State::TiledLeft);
+                state.states.set(xdg_toplevel::State::TiledRight);
+                state.states.set(xdg_toplevel::State::TiledTop);
+                state.states.set(xdg_toplevel::State::TiledBottom);
+            });
+            if toplevel.is_initial_configure_sent() {
+                toplevel.send_pending_configure();
+            }
+            size
+        }
+        #[cfg(feature = "xwayland")]
+        WindowSurface::X11(surface) => {
+            let size = constrain_size(
+                size,
+                surface.min_size().unwrap_or_default(),
+                surface.max_size().unwrap_or_default(),
+            );
+            let location =
+                cell.loc + Point::from(((cell.size.w - size.w) / 2, (cell.size.h - size.h - header) / 2));
+            let _ = surface.configure(Rectangle::new(location, size));
+            size
+        }
+    };
+
+    Size::from((size.w, size.h + header))
+}
+
+/// Clamps the pending size of a tiled toplevel to its current size constraints.
+///
+/// The constraints are only known once the client committed its initial state,
+/// so this has to be repeated right before the initial configure is sent.
+pub fn constrain_tiled_toplevel(toplevel: &ToplevelSurface) {
+    let (min_size, max_size) = toplevel_size_constraints(toplevel);
+    toplevel.with_pending_state(|state| {
+        if state.states.contains(xdg_toplevel::State::TiledLeft) {
+            state.size = state.size.map(|size| constrain_size(size, min_size, max_size));
+        }
+    });
+}
+
+pub(super) fn toplevel_size_constraints(
+    toplevel: &ToplevelSurface,
+) -> (Size<i32, Logical>, Size<i32, Logical>) {
+    with_states(toplevel.wl_surface(), |states| {
+        let mut guard = states.cached_state.get::<SurfaceCachedState>();
+        let data = guard.current();
+        (data.min_size, data.max_size)
+    })
+}
+
+/// Clamps a size to the given limits, a limit of zero means unbounded
+fn constrain_size(
+    size: Size<i32, Logical>,
+    min_size: Size<i32, Logical>,
+    max_size: Size<i32, Logical>,
+) -> Size<i32, Logical> {
+    let clamp = |value: i32, min: i32, max: i32| {
+        let value = if max > 0 { value.min(max) } else { value };
+        value.max(min).max(1)
+    };
+    Size::from((
+        clamp(size.w, min_size.w, max_size.w),
+        clamp(size.h, min_size.h, max_size.h),
+    ))
+}
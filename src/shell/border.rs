@@ -0,0 +1,97 @@
+use std::cell::RefCell;
+
+use smithay::{
+    backend::renderer::element::{
+        solid::{SolidColorBuffer, SolidColorRenderElement},
+        Kind,
+    },
+    desktop::space::SpaceElement,
+    utils::{Logical, Physical, Point, Rectangle, Scale, Size},
+};
+
+use super::WindowElement;
+
+/// Border around a window, one buffer per edge.
+///
+/// The border lies outside the bounding box of the window, it never takes
+/// input and isn't part of the geometry sent to clients.
+#[derive(Debug, Default)]
+struct WindowBorder {
+    edges: RefCell<[SolidColorBuffer; 4]>,
+}
+
+impl WindowElement {
+    fn border(&self) -> &WindowBorder {
+        self.user_data().insert_if_missing(WindowBorder::default);
+        self.user_data().get::<WindowBorder>().unwrap()
+    }
+
+    /// Width of the border drawn around the window, fullscreen windows have none
+    pub fn border_width(&self) -> i32 {
+        if self.is_fullscreen() && !self.is_fake_fullscreen() {
+            return 0;
+        }
+        crate::config::border().width.max(0)
+    }
+
+    /// Color of the border for the focus state of the window
+    pub fn border_color(&self) -> [f32; 4] {
+        let config = crate::config::border();
+        if self.urgency_blink() {
+            config.urgent
+        } else if self.is_focused() {
+            config.focused
+        } else {
+            config.unfocused
+        }
+    }
+
+    /// Edges of the border around the window geometry, front to back.
+    ///
+    /// `location` is where the window's surface is rendered.
+    pub fn border_elements(
+        &self,
+        location: Point<i32, Physical>,
+        scale: Scale<f64>,
+        alpha: f32,
+    ) -> Vec<SolidColorRenderElement> {
+        let width = self.border_width();
+        let geo = SpaceElement::geometry(self);
+        if width == 0 || geo.is_empty() {
+            return Vec::new();
+        }
+        let color = self.border_color();
+
+        let rects: [Rectangle<i32, Logical>; 4] = [
+            Rectangle::new(
+                geo.loc - Point::from((width, width)),
+                Size::from((geo.size.w + width * 2, width)),
+            ),
+            Rectangle::new(
+                geo.loc + Point::from((-width, geo.size.h)),
+                Size::from((geo.size.w + width * 2, width)),
+            ),
+            Rectangle::new(geo.loc - Point::from((width, 0)), Size::from((width, geo.size.h))),
+            Rectangle::new(
+                geo.loc + Point::from((geo.size.w, 0)),
+                Size::from((width, geo.size.h)),
+            ),
+        ];
+
+        let mut edges = self.border().edges.borrow_mut();
+        edges
+            .iter_mut()
+            .zip(rects)
+            .map(|(edge, rect)| {
+                edge.update(rect.size, color);
+                SolidColorRenderElement::from_buffer(
+                    edge,
+                    location + rect.loc.to_physical_precise_round(scale),
+                    scale,
+                    alpha,
+                    Kind::Unspecified,
+                )
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,124 @@
+use std::cell::RefCell;
+
+use smithay::{
+    backend::renderer::{element::Id, utils::CommitCounter},
+    desktop::space::SpaceElement,
+    utils::{Logical, Physical, Point, Rectangle, Scale, Size},
+};
+
+use crate::drawing::{EffectShaders, EffectsRenderer, ShadowElement};
+
+use super::WindowElement;
+
+/// Rounded corners and shadow of a window for the frame being rendered
+#[derive(Debug)]
+pub struct WindowEffects {
+    pub shaders: EffectShaders,
+    /// Window geometry, header bar included, in output coordinates
+    pub geometry: Rectangle<i32, Physical>,
+    pub corner_radius: i32,
+    shadow_size: i32,
+    shadow_offset: Point<i32, Physical>,
+    shadow_color: [f32; 4],
+}
+
+/// Shape of the shadow last drawn, a new shape damages the whole shadow
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct ShadowShape {
+    size: Size<i32, Physical>,
+    radius: i32,
+    blur: i32,
+    color: [f32; 4],
+}
+
+#[derive(Debug)]
+struct WindowShadow {
+    id: Id,
+    commit: CommitCounter,
+    shape: Option<ShadowShape>,
+}
+
+impl WindowElement {
+    /// Effects the window is drawn with, none if they are disabled, the window
+    /// is fullscreen or the renderer can't draw them
+    pub fn effects<R: EffectsRenderer>(
+        &self,
+        renderer: &mut R,
+        location: Point<i32, Physical>,
+        scale: Scale<f64>,
+    ) -> Option<WindowEffects> {
+        if self.is_fullscreen() && !self.is_fake_fullscreen() {
+            return None;
+        }
+        let config = crate::config::effects();
+        if config.corner_radius <= 0 && config.shadow_size <= 0 {
+            return None;
+        }
+        let geo = SpaceElement::geometry(self);
+        if geo.is_empty() {
+            return None;
+        }
+        let shaders = renderer.effect_shaders()?;
+
+        let geometry = Rectangle::new(
+            location + geo.loc.to_physical_precise_round(scale),
+            geo.size.to_physical_precise_round(scale),
+        );
+        let corner_radius = ((config.corner_radius.max(0) as f64 * scale.x).round() as i32)
+            .min(geometry.size.w / 2)
+            .min(geometry.size.h / 2);
+        let [x, y] = config.shadow_offset;
+        Some(WindowEffects {
+            shaders,
+            geometry,
+            corner_radius,
+            shadow_size: (config.shadow_size.max(0) as f64 * scale.x).round() as i32,
+            shadow_offset: Point::<i32, Logical>::from((x, y)).to_physical_precise_round(scale),
+            shadow_color: config.shadow_color,
+        })
+    }
+
+    /// Shadow behind the window, none if shadows are disabled
+    pub fn shadow_element(&self, effects: &WindowEffects, alpha: f32) -> Option<ShadowElement> {
+        if effects.shadow_size == 0 {
+            return None;
+        }
+        let shape = ShadowShape {
+            size: effects.geometry.size,
+            radius: effects.corner_radius,
+            blur: effects.shadow_size,
+            color: effects.shadow_color,
+        };
+
+        self.user_data().insert_if_missing(|| {
+            RefCell::new(WindowShadow {
+                id: Id::new(),
+                commit: CommitCounter::default(),
+                shape: None,
+            })
+        });
+        let mut shadow = self
+            .user_data()
+            .get::<RefCell<WindowShadow>>()
+            .unwrap()
+            .borrow_mut();
+        if shadow.shape != Some(shape) {
+            shadow.shape = Some(shape);
+            shadow.commit.increment();
+        }
+
+        Some(ShadowElement::new(
+            shadow.id.clone(),
+            shadow.commit,
+            effects.shaders.clone(),
+            Rectangle::new(
+                effects.geometry.loc + effects.shadow_offset,
+                effects.geometry.size,
+            ),
+            effects.corner_radius,
+            effects.shadow_size,
+            effects.shadow_color,
+            alpha,
+        ))
+    }
+}
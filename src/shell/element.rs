@@ -2,7 +2,9 @@ use std::{borrow::Cow, time::Duration};
 
 use smithay::{
     backend::renderer::{
-        element::{solid::SolidColorRenderElement, surface::WaylandSurfaceRenderElement, AsRenderElements},
+        element::{
+            solid::SolidColorRenderElement, surface::WaylandSurfaceRenderElement, AsRenderElements, Kind,
+        },
         ImportAll, ImportMem, Renderer, Texture,
     },
     desktop::{
@@ -23,12 +25,17 @@ use smithay::{
         wayland_server::protocol::wl_surface::WlSurface,
     },
     render_elements,
-    utils::{user_data::UserDataMap, IsAlive, Logical, Physical, Point, Rectangle, Scale, Serial},
+    utils::{user_data::UserDataMap, IsAlive, Logical, Physical, Point, Rectangle, Scale, Serial, Size},
     wayland::{compositor::SurfaceData as WlSurfaceData, dmabuf::DmabufFeedback, seat::WaylandFocus},
 };
 
 use super::ssd::HEADER_BAR_HEIGHT;
-use crate::{focus::PointerFocusTarget, state::Backend, AnvilState};
+use crate::{
+    drawing::{EffectsRenderer, RoundedElement, ShadowElement},
+    focus::PointerFocusTarget,
+    state::Backend,
+    AnvilState,
+};
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct WindowElement(pub Window);
@@ -379,7 +386,7 @@ impl SpaceElement for WindowElement {
         }
     }
     fn z_index(&self) -> u8 {
-        SpaceElement::z_index(&self.0)
+        self.stacking_z_index()
     }
 
     fn set_activate(&self, activated: bool) {
@@ -398,9 +405,12 @@ impl SpaceElement for WindowElement {
 }
 
 render_elements!(
-    pub WindowRenderElement<R> where R: ImportAll + ImportMem;
+    pub WindowRenderElement<R> where R: ImportAll + ImportMem + EffectsRenderer;
     Window=WaylandSurfaceRenderElement<R>,
     Decoration=SolidColorRenderElement,
+    RoundedWindow=RoundedElement<WaylandSurfaceRenderElement<R>>,
+    RoundedDecoration=RoundedElement<SolidColorRenderElement>,
+    Shadow=ShadowElement,
 );
 
 impl<R: Renderer> std::fmt::Debug for WindowRenderElement<R> {
@@ -408,6 +418,9 @@ impl<R: Renderer> std::fmt::Debug for WindowRenderElement<R> {
         match self {
             Self::Window(arg0) => f.debug_tuple("Window").field(arg0).finish(),
             Self::Decoration(arg0) => f.debug_tuple("Decoration").field(arg0).finish(),
+            Self::RoundedWindow(arg0) => f.debug_tuple("RoundedWindow").field(arg0).finish(),
+            Self::RoundedDecoration(arg0) => f.debug_tuple("RoundedDecoration").field(arg0).finish(),
+            Self::Shadow(arg0) => f.debug_tuple("Shadow").field(arg0).finish(),
             Self::_GenericCatcher(arg0) => f.debug_tuple("_GenericCatcher").field(arg0).finish(),
         }
     }
@@ -415,7 +428,7 @@ impl<R: Renderer> std::fmt::Debug for WindowRenderElement<R> {
 
 impl<R> AsRenderElements<R> for WindowElement
 where
-    R: Renderer + ImportAll + ImportMem,
+    R: Renderer + ImportAll + ImportMem + EffectsRenderer,
     R::TextureId: Clone + Texture + 'static,
 {
     type RenderElement = WindowRenderElement<R>;
@@ -423,37 +436,111 @@ where
     fn render_elements<C: From<Self::RenderElement>>(
         &self,
         renderer: &mut R,
-        mut location: Point<i32, Physical>,
+        location: Point<i32, Physical>,
         scale: Scale<f64>,
         alpha: f32,
     ) -> Vec<C> {
+        let alpha = alpha * self.opacity();
         let window_bbox = SpaceElement::bbox(&self.0);
+        let effects = self.effects(renderer, location, scale);
+        let corner_radius = effects.as_ref().map_or(0, |effects| effects.corner_radius);
+        // solid parts are clipped with the rounded shape of the window (or of its border)
+        let decoration = |element: SolidColorRenderElement,
+                          color: [f32; 4],
+                          geometry: Rectangle<i32, Physical>,
+                          radius: i32| match effects.as_ref() {
+            Some(effects) if corner_radius > 0 => {
+                WindowRenderElement::RoundedDecoration(RoundedElement::solid(
+                    element,
+                    effects.shaders.clone(),
+                    geometry,
+                    radius,
+                    scale,
+                    color,
+                    alpha,
+                ))
+            }
+            _ => WindowRenderElement::Decoration(element),
+        };
 
+        let mut vec: Vec<WindowRenderElement<R>> = Vec::new();
+        let mut surface_location = location;
         if self.decoration_state().is_ssd && !window_bbox.is_empty() {
             let window_geo = SpaceElement::geometry(&self.0);
 
             let mut state = self.decoration_state();
             let width = window_geo.size.w;
+            state.header_bar.stacking = self.stacking_class();
+            state.header_bar.urgent = self.urgency_blink();
             state.header_bar.redraw(width as u32);
-            let mut vec = AsRenderElements::<R>::render_elements::<WindowRenderElement<R>>(
-                &state.header_bar,
-                renderer,
-                location,
-                scale,
-                alpha,
-            );
+            if let Some(effects) = effects.as_ref() {
+                vec.extend(
+                    state
+                        .header_bar
+                        .parts()
+                        .into_iter()
+                        .map(|(buffer, offset, color)| {
+                            let element = SolidColorRenderElement::from_buffer(
+                                buffer,
+                                location + offset.to_physical_precise_round(scale),
+                                scale,
+                                alpha,
+                                Kind::Unspecified,
+                            );
+                            decoration(element, color, effects.geometry, corner_radius)
+                        }),
+                );
+            } else {
+                vec.extend(AsRenderElements::<R>::render_elements::<WindowRenderElement<R>>(
+                    &state.header_bar,
+                    renderer,
+                    location,
+                    scale,
+                    alpha,
+                ));
+            }
 
-            location.y += (scale.y * HEADER_BAR_HEIGHT as f64) as i32;
+            surface_location.y += (scale.y * HEADER_BAR_HEIGHT as f64) as i32;
+        }
 
-            let window_elements =
-                AsRenderElements::render_elements(&self.0, renderer, location, scale, alpha);
-            vec.extend(window_elements);
-            vec.into_iter().map(C::from).collect()
-        } else {
-            AsRenderElements::render_elements(&self.0, renderer, location, scale, alpha)
+        let window_elements: Vec<WaylandSurfaceRenderElement<R>> =
+            AsRenderElements::render_elements(&self.0, renderer, surface_location, scale, alpha);
+        vec.extend(window_elements.into_iter().map(|element| match effects.as_ref() {
+            Some(effects) if corner_radius > 0 => WindowRenderElement::RoundedWindow(RoundedElement::new(
+                element,
+                effects.shaders.clone(),
+                effects.geometry,
+                corner_radius,
+                scale,
+            )),
+            _ => WindowRenderElement::Window(element),
+        }));
+
+        let border_width = self.border_width();
+        let border_color = self.border_color();
+        vec.extend(
+            self.border_elements(location, scale, alpha)
                 .into_iter()
-                .map(C::from)
-                .collect()
+                .map(|element| {
+                    let Some(effects) = effects.as_ref() else {
+                        return WindowRenderElement::Decoration(element);
+                    };
+                    // the border follows the rounded corners of the window
+                    let width = (border_width as f64 * scale.x).round() as i32;
+                    let geometry = Rectangle::new(
+                        effects.geometry.loc - Point::from((width, width)),
+                        effects.geometry.size + Size::from((width * 2, width * 2)),
+                    );
+                    decoration(element, border_color, geometry, corner_radius + width)
+                }),
+        );
+
+        if let Some(shadow) = effects
+            .as_ref()
+            .and_then(|effects| self.shadow_element(effects, alpha))
+        {
+            vec.push(WindowRenderElement::Shadow(shadow));
         }
+        vec.into_iter().map(C::from).collect()
     }
 }
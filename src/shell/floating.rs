@@ -0,0 +1,237 @@
+use std::cell::Cell;
+
+use smithay::{
+    desktop::{layer_map_for_output, space::SpaceElement, Space, WindowSurface},
+    output::Output,
+    reexports::wayland_protocols::xdg::shell::server::xdg_toplevel,
+    utils::{Logical, Point, Rectangle, Size},
+};
+
+use crate::state::{AnvilState, Backend};
+
+use super::{
+    arrange_board, board::toplevel_size_constraints, map_with_transients, output_at, output_by_name,
+    parent_window, place_new_window, save_layout, WindowElement,
+};
+
+/// Floating state of a window
+#[derive(Debug, Default)]
+struct Floating {
+    floating: Cell<bool>,
+    /// The window was mapped before its size was known and still has to be centered
+    center_pending: Cell<bool>,
+}
+
+impl WindowElement {
+    fn floating_data(&self) -> &Floating {
+        self.user_data().insert_if_missing(Floating::default);
+        self.user_data().get::<Floating>().unwrap()
+    }
+
+    /// Whether the window floats above the board instead of occupying a cell
+    pub fn is_floating(&self) -> bool {
+        self.user_data()
+            .get::<Floating>()
+            .is_some_and(|data| data.floating.get())
+    }
+
+    pub fn set_floating(&self, floating: bool) {
+        self.floating_data().floating.set(floating);
+    }
+
+    /// Whether the window should float without being asked to:
+    /// it has a fixed size or is a dialog of another toplevel.
+    pub fn wants_floating(&self) -> bool {
+        if self.is_transient() {
+            return true;
+        }
+        let fixed = |min: Size<i32, Logical>, max: Size<i32, Logical>| min.w > 0 && min.h > 0 && min == max;
+        match self.0.underlying_surface() {
+            WindowSurface::Wayland(toplevel) => {
+                let (min_size, max_size) = toplevel_size_constraints(toplevel);
+                fixed(min_size, max_size)
+            }
+            #[cfg(feature = "xwayland")]
+            WindowSurface::X11(surface) => matches!(
+                (surface.min_size(), surface.max_size()),
+                (Some(min_size), Some(max_size)) if fixed(min_size, max_size)
+            ),
+        }
+    }
+}
+
+/// Visible area of the output not covered by exclusive layer surfaces
+pub(super) fn floating_area(
+    space: &Space<WindowElement>,
+    output: &Output,
+) -> Option<Rectangle<i32, Logical>> {
+    let geometry = space.output_geometry(output)?;
+    let zone = layer_map_for_output(output).non_exclusive_zone();
+    Some(Rectangle::new(geometry.loc + zone.loc, zone.size))
+}
+
+/// Area a floating window is centered on: its parent, or the output it floats on
+fn floating_anchor(space: &Space<WindowElement>, window: &WindowElement) -> Option<Rectangle<i32, Logical>> {
+    parent_window(space, window)
+        .and_then(|parent| space.element_geometry(&parent))
+        .or_else(|| {
+            let output = window.board_output(space)?;
+            floating_area(space, &output)
+        })
+}
+
+fn center_in(
+    space: &mut Space<WindowElement>,
+    window: &WindowElement,
+    area: Rectangle<i32, Logical>,
+    activate: bool,
+) {
+    let geometry = window.geometry();
+    let location = area.loc
+        + Point::from((
+            (area.size.w - geometry.size.w) / 2,
+            (area.size.h - geometry.size.h) / 2,
+        ))
+        - geometry.loc;
+    map_with_transients(space, window, location, activate);
+}
+
+/// Position the client asked for, only X11 clients get to pick one
+fn requested_location(window: &WindowElement) -> Option<Point<i32, Logical>> {
+    match window.0.underlying_surface() {
+        WindowSurface::Wayland(_) => None,
+        #[cfg(feature = "xwayland")]
+        WindowSurface::X11(surface) => {
+            let location = surface.geometry().loc;
+            (location != Point::default()).then_some(location)
+        }
+    }
+}
+
+/// Maps a floating window at the position from its rules, centered on its parent,
+/// at its requested position, or centered on the output.
+///
+/// A cell the window occupied before is released.
+pub fn place_floating(
+    space: &mut Space<WindowElement>,
+    output: Option<&Output>,
+    window: &WindowElement,
+    activate: bool,
+) {
+    let previous_board = window.board_cell().and(window.board_output(space));
+    window.set_board_cell(None);
+
+    // dialogs float on the output of the window that opened them
+    let rules = window.rule_properties();
+    let parent = parent_window(space, window);
+    let output = parent
+        .as_ref()
+        .and_then(|parent| parent.board_output(space))
+        .or_else(|| output_by_name(space, rules.output.as_deref()?))
+        .or_else(|| output.cloned());
+    let Some(output) = output.or_else(|| space.outputs().next().cloned()) else {
+        space.map_element(window.clone(), (0, 0), activate);
+        return;
+    };
+    window.set_board_output(Some(&output));
+    if let Some(parent) = parent.as_ref() {
+        // dialogs of an always-on-top window must not end up below it
+        window.set_stacking_class(parent.stacking_class());
+    }
+    let area = floating_area(space, &output).unwrap_or_else(|| Rectangle::from_size((800, 800).into()));
+
+    #[allow(irrefutable_let_patterns)]
+    if let Some(toplevel) = window.0.toplevel() {
+        toplevel.with_pending_state(|state| {
+            state.bounds = Some(area.size);
+            if let Some(size) = rules.size {
+                state.size = Some(size);
+            }
+            state.states.unset(xdg_toplevel::State::TiledLeft);
+            state.states.unset(xdg_toplevel::State::TiledRight);
+            state.states.unset(xdg_toplevel::State::TiledTop);
+            state.states.unset(xdg_toplevel::State::TiledBottom);
+        });
+        if toplevel.is_initial_configure_sent() {
+            toplevel.send_pending_configure();
+        }
+    }
+
+    #[cfg(feature = "xwayland")]
+    if let Some((surface, size)) = window.0.x11_surface().zip(rules.size) {
+        let _ = surface.configure(Rectangle::new(surface.geometry().loc, size));
+    }
+
+    let requested = rules.position.map(|position| area.loc + position).or_else(|| {
+        requested_location(window).filter(|location| parent.is_none() && area.contains(*location))
+    });
+    match requested {
+        Some(location) => map_with_transients(space, window, location, activate),
+        None => {
+            let anchor = floating_anchor(space, window).unwrap_or(area);
+            center_in(space, window, anchor, activate);
+        }
+    }
+    let size = window.geometry().size;
+    window
+        .floating_data()
+        .center_pending
+        .set(requested.is_none() && (size.w <= 0 || size.h <= 0));
+
+    #[cfg(feature = "xwayland")]
+    if let Some(surface) = window.0.x11_surface() {
+        if let Some(bbox) = space.element_bbox(window) {
+            let _ = surface.configure(bbox);
+        }
+    }
+
+    // the board may shrink without the window
+    if let Some(previous_board) = previous_board {
+        let tiled_sizing = crate::config::layout().tiled_sizing;
+        arrange_board(space, &previous_board, tiled_sizing);
+    }
+    save_layout();
+}
+
+/// Centers a floating window mapped before its size was known, once it has one
+pub fn finish_floating_placement(space: &mut Space<WindowElement>, window: &WindowElement) {
+    let Some(data) = window.user_data().get::<Floating>() else {
+        return;
+    };
+    let size = window.geometry().size;
+    if !data.center_pending.get() || size.w <= 0 || size.h <= 0 {
+        return;
+    }
+    data.center_pending.set(false);
+    // the window was put into a cell meanwhile
+    if window.board_cell().is_some() {
+        return;
+    }
+
+    if let Some(anchor) = floating_anchor(space, window) {
+        center_in(space, window, anchor, false);
+    }
+}
+
+impl<BackendData: Backend> AnvilState<BackendData> {
+    /// Takes the focused window off the board, or puts a floating one back into a cell
+    pub fn toggle_floating(&mut self) {
+        let Some(window) = self.focused_window().filter(|window| !window.is_scratchpad()) else {
+            return;
+        };
+        let output = window
+            .board_output(&self.space)
+            .or_else(|| output_at(&self.space, self.pointer.current_location()));
+
+        let floating = !window.is_floating();
+        window.set_floating(floating);
+        if floating {
+            place_floating(&mut self.space, output.as_ref(), &window, true);
+        } else {
+            place_new_window(&mut self.space, output.as_ref(), &window, true);
+        }
+
+        let delta = self.reveal_window(&window);
+        self.warp_pointer_to(&window, delta);
+    }
+}
@@ -0,0 +1,253 @@
+use std::cell::{Cell, RefCell};
+
+use smithay::{
+    desktop::WindowSurface,
+    output::Output,
+    reexports::{wayland_protocols::xdg::shell::server::xdg_toplevel, wayland_server::Resource},
+    utils::{Logical, Rectangle, Size},
+};
+use tracing::trace;
+
+use crate::{
+    config::FullscreenPolicy,
+    state::{AnvilState, Backend},
+};
+
+use super::{return_to_placement, FullscreenSurface, SavedPlacement, WindowElement};
+
+/// What the compositor did when the window went fullscreen
+#[derive(Debug, Default)]
+struct Fullscreened {
+    /// Placement from before, the window is off the board while fullscreen
+    placement: RefCell<Option<SavedPlacement>>,
+    /// The client was told it is fullscreen, but the window stayed where it was
+    fake: Cell<bool>,
+}
+
+impl WindowElement {
+    fn fullscreened(&self) -> &Fullscreened {
+        self.user_data().insert_if_missing(Fullscreened::default);
+        self.user_data().get::<Fullscreened>().unwrap()
+    }
+
+    /// Whether the window covers an output or pretends to
+    pub fn is_fullscreen(&self) -> bool {
+        self.user_data()
+            .get::<Fullscreened>()
+            .is_some_and(|fullscreened| fullscreened.fake.get() || fullscreened.placement.borrow().is_some())
+    }
+
+    /// Whether the client was told it is fullscreen while the window stays in its cell
+    pub fn is_fake_fullscreen(&self) -> bool {
+        self.user_data()
+            .get::<Fullscreened>()
+            .is_some_and(|fullscreened| fullscreened.fake.get())
+    }
+
+    fn current_size(&self) -> Option<Size<i32, Logical>> {
+        match self.0.underlying_surface() {
+            WindowSurface::Wayland(toplevel) => toplevel.current_state().size,
+            #[cfg(feature = "xwayland")]
+            WindowSurface::X11(surface) => Some(surface.geometry().size),
+        }
+    }
+}
+
+impl<BackendData: Backend> AnvilState<BackendData> {
+    /// Makes the window cover the output, the one it is on if none is given.
+    ///
+    /// Its cell is kept free until it leaves fullscreen again. Windows with the `fake_fullscreen`
+    /// rule are only told they are fullscreen and stay where they are.
+    pub fn fullscreen_window(&mut self, window: &WindowElement, output: Option<Output>) {
+        if window.is_fullscreen() {
+            self.send_fullscreen_configure(window);
+            return;
+        }
+        let output = output
+            .or_else(|| self.space.outputs_for_element(window).first().cloned())
+            // The window hasn't been mapped yet, use the primary output instead
+            .or_else(|| self.space.outputs().next().cloned());
+        let Some((output, geometry)) = output.and_then(|output| {
+            self.space
+                .output_geometry(&output)
+                .map(|geometry| (output, geometry))
+        }) else {
+            self.send_fullscreen_configure(window);
+            return;
+        };
+
+        if window.rule_properties().fake_fullscreen {
+            trace!("Fake fullscreening: {:?}", window);
+            window.fullscreened().fake.set(true);
+            self.set_client_fullscreen(window, Some(&output), None);
+            self.send_fullscreen_configure(window);
+            return;
+        }
+
+        if crate::config::layout().fullscreen_policy == FullscreenPolicy::Replace {
+            let previous = output
+                .user_data()
+                .get::<FullscreenSurface>()
+                .and_then(|fullscreen| fullscreen.get());
+            if let Some(previous) = previous {
+                self.unfullscreen_window(&previous);
+            }
+        }
+
+        trace!("Fullscreening: {:?}", window);
+        *window.fullscreened().placement.borrow_mut() =
+            Some(SavedPlacement::save(&self.space, window, window.current_size()));
+        output.user_data().insert_if_missing(FullscreenSurface::default);
+        output
+            .user_data()
+            .get::<FullscreenSurface>()
+            .unwrap()
+            .push(window.clone());
+        self.set_client_fullscreen(window, Some(&output), Some(geometry));
+        self.send_fullscreen_configure(window);
+        self.backend_data.reset_buffers(&output);
+    }
+
+    /// Returns a fullscreen window to its cell, or where it floated before
+    pub fn unfullscreen_window(&mut self, window: &WindowElement) {
+        if window.is_fake_fullscreen() {
+            trace!("Fake unfullscreening: {:?}", window);
+            window.fullscreened().fake.set(false);
+            self.set_client_fullscreen(window, None, None);
+            self.send_fullscreen_configure(window);
+            return;
+        }
+        let Some(placement) = self.leave_fullscreen(window) else {
+            return;
+        };
+
+        trace!("Unfullscreening: {:?}", window);
+        let size = placement.size;
+        let location = placement.restore(&self.space, window);
+        if let Some(toplevel) = window.0.toplevel() {
+            toplevel.with_pending_state(|state| state.size = size);
+        }
+        let geometry = size.zip(location).map(|(size, loc)| Rectangle::new(loc, size));
+        self.set_client_fullscreen(window, None, geometry);
+        return_to_placement(&mut self.space, window, location);
+        #[cfg(feature = "xwayland")]
+        if let Some(surface) = window.0.x11_surface() {
+            let _ = surface.configure(self.space.element_bbox(window));
+        }
+        self.send_fullscreen_configure(window);
+    }
+
+    /// Fullscreens or unfullscreens the focused window
+    pub fn toggle_fullscreen(&mut self) {
+        let Some(window) = self.focused_window() else {
+            return;
+        };
+        if window.is_fullscreen() {
+            self.unfullscreen_window(&window);
+        } else {
+            self.fullscreen_window(&window, None);
+        }
+    }
+
+    /// Brings a fullscreen window covered by another one on its output back to the front
+    pub fn raise_fullscreen(&mut self, window: &WindowElement) {
+        let output = self.space.outputs().find(|output| {
+            output
+                .user_data()
+                .get::<FullscreenSurface>()
+                .is_some_and(|fullscreen| {
+                    fullscreen.contains(window) && fullscreen.get().as_ref() != Some(window)
+                })
+        });
+        if let Some(output) = output.cloned() {
+            output
+                .user_data()
+                .get::<FullscreenSurface>()
+                .unwrap()
+                .push(window.clone());
+            self.backend_data.reset_buffers(&output);
+        }
+    }
+
+    /// Takes a window that went away out of fullscreen, its cell becomes free
+    pub fn forget_fullscreen(&mut self, window: &WindowElement) {
+        if let Some(placement) = self.leave_fullscreen(window) {
+            placement.release(&self.space);
+        }
+    }
+
+    /// Removes the window from the fullscreen windows of its output, the one below is shown again
+    fn leave_fullscreen(&mut self, window: &WindowElement) -> Option<SavedPlacement> {
+        let placement = window
+            .user_data()
+            .get::<Fullscreened>()
+            .and_then(|fullscreened| fullscreened.placement.borrow_mut().take());
+        let outputs = self
+            .space
+            .outputs()
+            .filter(|output| {
+                output
+                    .user_data()
+                    .get::<FullscreenSurface>()
+                    .is_some_and(|fullscreen| fullscreen.remove(window))
+            })
+            .cloned()
+            .collect::<Vec<_>>();
+        for output in outputs {
+            self.backend_data.reset_buffers(&output);
+        }
+        placement
+    }
+
+    /// Sets the fullscreen state the client sees, it is configured with `geometry` if given
+    fn set_client_fullscreen(
+        &mut self,
+        window: &WindowElement,
+        output: Option<&Output>,
+        geometry: Option<Rectangle<i32, Logical>>,
+    ) {
+        match window.0.underlying_surface() {
+            WindowSurface::Wayland(toplevel) => {
+                let fullscreen_output = output.and_then(|output| {
+                    let client = self.display_handle.get_client(toplevel.wl_surface().id()).ok()?;
+                    output.client_outputs(&client).into_iter().last()
+                });
+                toplevel.with_pending_state(|state| {
+                    if output.is_some() {
+                        state.states.set(xdg_toplevel::State::Fullscreen);
+                    } else {
+                        state.states.unset(xdg_toplevel::State::Fullscreen);
+                    }
+                    // a fake fullscreen window keeps the size of its cell
+                    if let Some(geometry) = geometry {
+                        state.size = Some(geometry.size);
+                    }
+                    state.fullscreen_output = fullscreen_output;
+                });
+            }
+            #[cfg(feature = "xwayland")]
+            WindowSurface::X11(surface) => {
+                let fullscreen = output.is_some();
+                let _ = surface.set_fullscreen(fullscreen);
+                if fullscreen && geometry.is_some() {
+                    window.set_ssd(false);
+                } else if !fullscreen {
+                    window.set_ssd(window.rule_properties().ssd.unwrap_or(!surface.is_decorated()));
+                }
+                if let Some(geometry) = geometry {
+                    let _ = surface.configure(geometry);
+                }
+            }
+        }
+    }
+
+    fn send_fullscreen_configure(&mut self, window: &WindowElement) {
+        if let Some(toplevel) = window.0.toplevel() {
+            // The protocol demands us to always reply with a configure,
+            // regardless of we fulfilled the request or not
+            if toplevel.is_initial_configure_sent() {
+                toplevel.send_configure();
+            }
+        }
+    }
+}
@@ -0,0 +1,176 @@
+use std::{fs, path::PathBuf, sync::Mutex, time::Duration};
+
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use smithay::{desktop::Space, output::Output};
+use tracing::{debug, warn};
+
+use crate::state::{AnvilState, Backend};
+
+use super::{board_windows, window_identity, OutputIdentity, WindowElement};
+
+/// Cell a window occupied when the layout was saved
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+struct SavedCell {
+    /// xdg app_id, or WM_CLASS for X11 windows
+    app_id: String,
+    title: String,
+    connector: String,
+    make: String,
+    model: String,
+    cell: usize,
+}
+
+impl SavedCell {
+    fn identity(&self) -> OutputIdentity {
+        OutputIdentity {
+            connector: self.connector.clone(),
+            make: self.make.clone(),
+            model: self.model.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct SavedLayout {
+    /// Cells from the state file no window has been put back into yet
+    pending: Vec<SavedCell>,
+    /// Cells last written to the state file
+    written: Vec<SavedCell>,
+    /// Whether the layout changed since it was last written, the state file is
+    /// only written every `SAVE_INTERVAL`
+    changed: bool,
+}
+
+/// Interval the state file is written in at most, the layout changes on every placement
+pub const SAVE_INTERVAL: Duration = Duration::from_secs(1);
+
+// Saved board layout, loaded from the state file on first use
+static SAVED_LAYOUT: Lazy<Mutex<SavedLayout>> = Lazy::new(|| {
+    let pending = load_layout();
+    Mutex::new(SavedLayout {
+        written: pending.clone(),
+        pending,
+        changed: false,
+    })
+});
+
+fn state_file() -> Option<PathBuf> {
+    let state_home = std::env::var_os("XDG_STATE_HOME")
+        .map(PathBuf::from)
+        .filter(|path| path.is_absolute())
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/state")))?;
+    Some(state_home.join("anvil").join("board.json"))
+}
+
+fn load_layout() -> Vec<SavedCell> {
+    let Some(path) = state_file() else {
+        return Vec::new();
+    };
+    match fs::read_to_string(&path) {
+        Ok(contents) => serde_json::from_str(&contents).unwrap_or_else(|err| {
+            warn!(?path, "Failed to parse saved board layout: {}", err);
+            Vec::new()
+        }),
+        Err(_) => Vec::new(),
+    }
+}
+
+/// app_id (or WM_CLASS) and title identifying the window across restarts
+fn window_key(window: &WindowElement) -> Option<(String, String)> {
+    let (app_id, title) = window_identity(window);
+    (!app_id.is_empty()).then_some((app_id, title))
+}
+
+/// Takes the saved cell for the window out of the state file, if its output is connected
+/// and the cell is still free.
+///
+/// A cell saved for the same app_id and title wins over one only matching the app_id.
+pub fn take_saved_cell(space: &Space<WindowElement>, window: &WindowElement) -> Option<(Output, usize)> {
+    let (app_id, title) = window_key(window)?;
+    let mut layout = SAVED_LAYOUT.lock().unwrap();
+
+    let usable = |saved: &SavedCell| {
+        let output = saved.identity().find(space)?;
+        let occupied =
+            board_windows(space, &output).any(|w| w != window && w.board_cell() == Some(saved.cell));
+        (!occupied).then_some(output)
+    };
+    let position = layout
+        .pending
+        .iter()
+        .position(|saved| saved.app_id == app_id && saved.title == title && usable(saved).is_some())
+        .or_else(|| {
+            layout
+                .pending
+                .iter()
+                .position(|saved| saved.app_id == app_id && usable(saved).is_some())
+        })?;
+
+    let saved = layout.pending.remove(position);
+    debug!(app_id, title, cell = saved.cell, "Restoring saved board cell");
+    Some((usable(&saved)?, saved.cell))
+}
+
+/// Marks the board layout as changed, `AnvilState::flush_layout` writes it to the state file
+pub fn save_layout() {
+    SAVED_LAYOUT.lock().unwrap().changed = true;
+}
+
+impl<BackendData: Backend> AnvilState<BackendData> {
+    /// Writes the cells of all windows on a board to the state file, if the layout changed.
+    ///
+    /// Minimized windows keeping their cell free and windows maximized or fullscreened out
+    /// of their cell are included. Saved cells not restored yet are kept, their apps might
+    /// not have been started again.
+    pub fn flush_layout(&self) {
+        let mut layout = SAVED_LAYOUT.lock().unwrap();
+        if !std::mem::take(&mut layout.changed) {
+            return;
+        }
+        let Some(path) = state_file() else {
+            return;
+        };
+
+        let mut cells = self
+            .space
+            .elements()
+            .chain(self.minimized.iter())
+            .filter_map(|window| {
+                let (app_id, title) = window_key(window)?;
+                let (identity, cell) = window.held_cell()?;
+                Some(SavedCell {
+                    app_id,
+                    title,
+                    connector: identity.connector,
+                    make: identity.make,
+                    model: identity.model,
+                    cell,
+                })
+            })
+            .collect::<Vec<_>>();
+        let pending = layout
+            .pending
+            .iter()
+            .filter(|pending| {
+                !cells
+                    .iter()
+                    .any(|cell| cell.identity() == pending.identity() && cell.cell == pending.cell)
+            })
+            .cloned()
+            .collect::<Vec<_>>();
+        cells.extend(pending);
+        if cells == layout.written {
+            return;
+        }
+
+        let result = fs::create_dir_all(path.parent().unwrap()).and_then(|_| {
+            let contents = serde_json::to_string_pretty(&cells).map_err(std::io::Error::other)?;
+            fs::write(&path, contents)
+        });
+        match result {
+            Ok(()) => layout.written = cells,
+            Err(err) => warn!(?path, "Failed to save board layout: {}", err),
+        }
+    }
+}
@@ -0,0 +1,97 @@
+use std::cell::RefCell;
+
+use smithay::{
+    reexports::wayland_protocols::xdg::shell::server::xdg_toplevel,
+    utils::{Logical, Point, Size},
+    wayland::shell::xdg::ToplevelSurface,
+};
+
+use crate::state::{AnvilState, Backend};
+
+use super::{
+    floating_area, map_with_transients, return_to_placement, ssd::HEADER_BAR_HEIGHT, SavedPlacement,
+    WindowElement,
+};
+
+/// Placement of a xdg toplevel before it got maximized
+#[derive(Debug, Default)]
+struct Unmaximized(RefCell<Option<SavedPlacement>>);
+
+impl WindowElement {
+    fn unmaximized(&self) -> &Unmaximized {
+        self.user_data().insert_if_missing(Unmaximized::default);
+        self.user_data().get::<Unmaximized>().unwrap()
+    }
+}
+
+impl<BackendData: Backend> AnvilState<BackendData> {
+    /// Sizes the toplevel to the area of its output not covered by exclusive layer surfaces.
+    ///
+    /// Its cell is kept free and its location and size are remembered for unmaximizing.
+    pub fn maximize_xdg(&mut self, surface: &ToplevelSurface, window: &WindowElement) {
+        let output = self
+            .space
+            .outputs_for_element(window)
+            .first()
+            .cloned()
+            // The window hasn't been mapped yet, use the primary output instead
+            .or_else(|| self.space.outputs().next().cloned());
+        let Some(area) = output.and_then(|output| floating_area(&self.space, &output)) else {
+            return;
+        };
+
+        let mut unmaximized = window.unmaximized().0.borrow_mut();
+        if unmaximized.is_none() {
+            *unmaximized = Some(SavedPlacement::save(
+                &self.space,
+                window,
+                surface.current_state().size,
+            ));
+        }
+        drop(unmaximized);
+
+        let header = if window.decoration_state().is_ssd {
+            HEADER_BAR_HEIGHT
+        } else {
+            0
+        };
+        surface.with_pending_state(|state| {
+            state.states.set(xdg_toplevel::State::Maximized);
+            state.size = Some(Size::from((area.size.w, area.size.h - header)));
+        });
+        map_with_transients(&mut self.space, window, area.loc, true);
+    }
+
+    /// Gives a maximized toplevel its size and cell from before back.
+    ///
+    /// Returns where the window was, the caller decides whether it goes back there.
+    pub fn restore_unmaximized(
+        &mut self,
+        surface: &ToplevelSurface,
+        window: &WindowElement,
+    ) -> Option<Point<i32, Logical>> {
+        let unmaximized = window.unmaximized().0.borrow_mut().take();
+        surface.with_pending_state(|state| {
+            state.states.unset(xdg_toplevel::State::Maximized);
+            state.size = unmaximized.as_ref().and_then(|unmaximized| unmaximized.size);
+        });
+        unmaximized?.restore(&self.space, window)
+    }
+
+    /// Puts an unmaximized toplevel back into its cell, or where it floated before
+    pub fn unmaximize_xdg(&mut self, surface: &ToplevelSurface, window: &WindowElement) {
+        let location = self.restore_unmaximized(surface, window);
+        return_to_placement(&mut self.space, window, location);
+    }
+
+    /// Frees the cell kept for a maximized window that went away
+    pub fn forget_unmaximized(&mut self, window: &WindowElement) {
+        let unmaximized = window
+            .user_data()
+            .get::<Unmaximized>()
+            .and_then(|unmaximized| unmaximized.0.borrow_mut().take());
+        if let Some(unmaximized) = unmaximized {
+            unmaximized.release(&self.space);
+        }
+    }
+}
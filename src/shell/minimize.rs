@@ -0,0 +1,149 @@
+use std::cell::RefCell;
+
+use smithay::utils::{IsAlive, SERIAL_COUNTER};
+
+use crate::{
+    config::MinimizedCell,
+    state::{AnvilState, Backend},
+};
+
+use super::{
+    place_floating, place_new_window, reserve_cell, save_layout, transients_of, unreserve_cell, WindowElement,
+};
+
+/// Dialogs hidden along with a minimized window
+#[derive(Debug, Default)]
+struct HiddenTransients(RefCell<Vec<WindowElement>>);
+
+impl WindowElement {
+    fn hidden_transients(&self) -> &HiddenTransients {
+        self.user_data().insert_if_missing(HiddenTransients::default);
+        self.user_data().get::<HiddenTransients>().unwrap()
+    }
+}
+
+/// Tells X11 clients whether their window is iconified
+pub(super) fn set_hidden(window: &WindowElement, hidden: bool) {
+    #[cfg(feature = "xwayland")]
+    if let Some(surface) = window.0.x11_surface() {
+        let _ = surface.set_hidden(hidden);
+    }
+    #[cfg(not(feature = "xwayland"))]
+    let _ = (window, hidden);
+}
+
+impl<BackendData: Backend> AnvilState<BackendData> {
+    /// Unmaps the window and its dialogs, the window stays in the list of minimized windows.
+    ///
+    /// Its cell is released or kept free until the window is restored, as `layout.minimized_cell` says.
+    pub fn minimize_window(&mut self, window: &WindowElement) {
+        if self.space.element_location(window).is_none() || self.minimized.contains(window) {
+            return;
+        }
+        // a minimized window doesn't cover its output anymore
+        if window.is_fullscreen() {
+            self.unfullscreen_window(window);
+        }
+        let focused = self.focused_window();
+        let transients = transients_of(&self.space, window);
+        let had_focus = focused.is_some_and(|focused| focused == *window || transients.contains(&focused));
+
+        for transient in &transients {
+            self.space.unmap_elem(transient);
+            self.focus_stack.retain(|w| w != transient);
+            set_hidden(transient, true);
+        }
+        let reserve = crate::config::layout().minimized_cell == MinimizedCell::Reserve;
+        let board = window.board_output(&self.space).zip(window.board_cell());
+        match board {
+            Some((output, index)) if reserve => {
+                reserve_cell(&output, index);
+                self.space.unmap_elem(window);
+                self.focus_stack.retain(|w| w != window);
+                save_layout();
+                self.update_idle_inhibit();
+            }
+            _ => {
+                // the cell is given up like the one of a closed window
+                self.window_unmapped(window);
+                window.set_board_cell(None);
+            }
+        }
+        set_hidden(window, true);
+        *window.hidden_transients().0.borrow_mut() = transients;
+        self.minimized.push(window.clone());
+        self.sync_overview_minimized();
+
+        if had_focus {
+            self.focus_previous();
+        }
+    }
+
+    /// Maps a minimized window again, into its reserved cell if nothing took it meanwhile
+    pub fn restore_window(&mut self, window: &WindowElement) {
+        let Some(position) = self.minimized.iter().position(|w| w == window) else {
+            return;
+        };
+        self.minimized.remove(position);
+
+        let board_output = window.board_output(&self.space);
+        match board_output.as_ref().zip(window.board_cell()) {
+            Some((output, index)) => {
+                unreserve_cell(output, index);
+                if self.window_in_cell(output, index).is_some() {
+                    window.set_board_cell(None);
+                }
+            }
+            // the output went away while the window was hidden
+            None => window.set_board_cell(None),
+        }
+        let output = board_output.or_else(|| self.new_window_output());
+
+        set_hidden(window, false);
+        place_new_window(&mut self.space, output.as_ref(), window, true);
+        let transients = window.hidden_transients().0.take();
+        for transient in transients.iter().filter(|transient| transient.alive()) {
+            set_hidden(transient, false);
+            place_floating(&mut self.space, output.as_ref(), transient, false);
+        }
+        self.sync_overview_minimized();
+
+        self.focus_window(window);
+        self.reveal_window(window);
+    }
+
+    /// Restores the window minimized last
+    pub fn restore_last(&mut self) {
+        if let Some(window) = self.minimized.last().cloned() {
+            self.restore_window(&window);
+        }
+    }
+
+    /// Forgets a minimized window that went away, its reserved cell becomes free
+    pub fn forget_minimized(&mut self, window: &WindowElement) {
+        let Some(position) = self.minimized.iter().position(|w| w == window) else {
+            return;
+        };
+        self.minimized.remove(position);
+        if let Some((output, index)) = window.board_output(&self.space).zip(window.board_cell()) {
+            unreserve_cell(&output, index);
+        }
+        self.sync_overview_minimized();
+    }
+
+    /// Moves the keyboard focus to the most recently focused window still shown
+    pub fn focus_previous(&mut self) {
+        let previous = self
+            .focus_stack
+            .iter()
+            .find(|w| self.space.element_location(w).is_some() && w.accepts_focus())
+            .cloned();
+        match previous {
+            Some(window) => self.focus_window(&window),
+            None => {
+                let keyboard = self.seat.get_keyboard().unwrap();
+                keyboard.set_focus(self, None, SERIAL_COUNTER.next_serial());
+            }
+        }
+    }
+}
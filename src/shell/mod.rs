@@ -21,7 +21,7 @@ use smithay::{
             Client, Resource,
         },
     },
-    utils::{IsAlive, Logical, Point, Rectangle, Size},
+    utils::{IsAlive, Logical, Point, Rectangle, Size, SERIAL_COUNTER},
     wayland::{
         buffer::BufferHandler,
         compositor::{
@@ -41,57 +41,91 @@ use smithay::{
 };
 
 use crate::{
+    config::NewWindowOutput,
     state::{AnvilState, Backend},
     ClientState,
 };
 
+mod activation;
+mod board;
+mod border;
+mod effects;
 mod element;
+mod floating;
+mod fullscreen;
 mod grabs;
+mod layout_store;
+mod maximize;
+mod minimize;
+mod navigation;
+mod opacity;
+mod overview;
+mod placement;
+mod pointer_focus;
+mod rules;
+mod scratchpad;
 pub(crate) mod ssd;
+mod stacking;
+mod switcher;
+mod transient;
+mod urgency;
+mod viewport;
 #[cfg(feature = "xwayland")]
 mod x11;
 mod xdg;
 
+pub use self::activation::*;
+pub use self::board::*;
+pub use self::border::*;
+pub use self::effects::*;
 pub use self::element::*;
+pub use self::floating::*;
+pub use self::fullscreen::*;
 pub use self::grabs::*;
-
-fn fullscreen_output_geometry(
-    wl_surface: &WlSurface,
-    wl_output: Option<&wl_output::WlOutput>,
-    space: &mut Space<WindowElement>,
-) -> Option<Rectangle<i32, Logical>> {
-    // First test if a specific output has been requested
-    // if the requested output is not found ignore the request
-    wl_output
-        .and_then(Output::from_resource)
-        .or_else(|| {
-            let w = space
-                .elements()
-                .find(|window| window.wl_surface().map(|s| &*s == wl_surface).unwrap_or(false));
-            w.and_then(|w| space.outputs_for_element(w).first().cloned())
-        })
-        .as_ref()
-        .and_then(|o| space.output_geometry(o))
-}
-
+pub use self::layout_store::*;
+pub use self::maximize::*;
+pub use self::minimize::*;
+pub use self::navigation::*;
+pub use self::opacity::*;
+pub use self::overview::*;
+pub use self::placement::*;
+pub use self::pointer_focus::*;
+pub use self::rules::*;
+pub use self::scratchpad::*;
+pub use self::stacking::*;
+pub use self::switcher::*;
+pub use self::transient::*;
+pub use self::urgency::*;
+pub use self::viewport::*;
+
+/// Windows fullscreened on an output, the last one covers it
 #[derive(Default)]
-pub struct FullscreenSurface(RefCell<Option<WindowElement>>);
+pub struct FullscreenSurface(RefCell<Vec<WindowElement>>);
 
 impl FullscreenSurface {
-    pub fn set(&self, window: WindowElement) {
-        *self.0.borrow_mut() = Some(window);
+    /// Puts the window on top of the others fullscreened on the output
+    pub fn push(&self, window: WindowElement) {
+        let mut windows = self.0.borrow_mut();
+        windows.retain(|w| w != &window);
+        windows.push(window);
     }
 
     pub fn get(&self) -> Option<WindowElement> {
-        let mut window = self.0.borrow_mut();
-        if window.as_ref().map(|w| !w.alive()).unwrap_or(false) {
-            *window = None;
-        }
-        window.clone()
+        let mut windows = self.0.borrow_mut();
+        windows.retain(|w| w.alive());
+        windows.last().cloned()
+    }
+
+    pub fn contains(&self, window: &WindowElement) -> bool {
+        self.0.borrow().contains(window)
     }
 
-    pub fn clear(&self) -> Option<WindowElement> {
-        self.0.borrow_mut().take()
+    /// Removes the window, returns whether it was fullscreened on the output
+    pub fn remove(&self, window: &WindowElement) -> bool {
+        let mut windows = self.0.borrow_mut();
+        let len = windows.len();
+        windows.retain(|w| w != window);
+        windows.len() != len
     }
 }
 
@@ -238,7 +272,26 @@ impl<BackendData: Backend> CompositorHandler for AnvilState<BackendData> {
             });
         }
 
-        ensure_initial_configure(surface, &self.space, &mut self.popups)
+        // app_id and title of xdg toplevels are only known once the initial configure is due
+        if let Some(window) = self.window_for_surface(surface).filter(|window| {
+            window
+                .0
+                .toplevel()
+                .is_some_and(|toplevel| !toplevel.is_initial_configure_sent())
+        }) {
+            // adopted first, so the rules don't put a scratchpad window into a cell
+            let adopted = self.adopt_pending_scratchpad(&window);
+            self.apply_window_rules(&window);
+            if adopted {
+                self.show_scratchpad(&window);
+            } else if window.board_cell().is_none() && !window.is_floating() {
+                // placed off the board while a scratchpad was pending
+                let output = self.new_window_output();
+                place_new_window(&mut self.space, output.as_ref(), &window, true);
+            }
+        }
+
+        ensure_initial_configure(surface, &mut self.space, &mut self.popups)
     }
 }
 
@@ -283,6 +336,37 @@ impl<BackendData: Backend> AnvilState<BackendData> {
             .find(|window| window.wl_surface().map(|s| &*s == surface).unwrap_or(false))
             .cloned()
     }
+
+    /// Raises the window along with its dialogs and gives it keyboard focus
+    pub fn focus_window(&mut self, window: &WindowElement) {
+        self.space.raise_element(window, true);
+        // dialogs stay on top of the window that opened them
+        for transient in transients_of(&self.space, window) {
+            self.space.raise_element(&transient, false);
+        }
+        self.raise_fullscreen(window);
+        #[cfg(feature = "xwayland")]
+        if let Some(surface) = window.0.x11_surface() {
+            self.xwm.as_mut().unwrap().raise_window(surface).unwrap();
+        }
+        if !window.accepts_focus() {
+            return;
+        }
+        let keyboard = self.seat.get_keyboard().unwrap();
+        keyboard.set_focus(self, Some(window.clone().into()), SERIAL_COUNTER.next_serial());
+    }
+
+    /// Output whose board a new window joins
+    pub fn new_window_output(&self) -> Option<Output> {
+        let pointer_output = || output_at(&self.space, self.pointer.current_location());
+        match crate::config::layout().new_window_output {
+            NewWindowOutput::Pointer => pointer_output(),
+            NewWindowOutput::Focus => self
+                .focused_window()
+                .and_then(|window| window.board_output(&self.space))
+                .or_else(pointer_output),
+        }
+    }
 }
 
 #[derive(Default)]
@@ -291,7 +375,11 @@ pub struct SurfaceData {
     pub resize_state: ResizeState,
 }
 
-fn ensure_initial_configure(surface: &WlSurface, space: &Space<WindowElement>, popups: &mut PopupManager) {
+fn ensure_initial_configure(
+    surface: &WlSurface,
+    space: &mut Space<WindowElement>,
+    popups: &mut PopupManager,
+) {
     with_surface_tree_upward(
         surface,
         (),
@@ -322,6 +410,23 @@ fn ensure_initial_configure(surface: &WlSurface, space: &Space<WindowElement>, p
                     .initial_configure_sent
             });
             if !initial_configure_sent {
+                let rules = window.rule_properties();
+                if rules.floating.is_none() && !window.is_floating() && window.wants_floating() {
+                    // size limits and parent are only known now, take the window off the board again
+                    let output = window.board_output(space);
+                    window.set_floating(true);
+                    place_new_window(space, output.as_ref(), &window, false);
+                } else if let Some((output, index)) =
+                    (!window.is_floating() && rules.output.is_none() && rules.cell.is_none())
+                        .then(|| take_saved_cell(space, &window))
+                        .flatten()
+                {
+                    // app_id and title are only known now, put the window back where it was before a restart
+                    window.set_board_output(Some(&output));
+                    window.set_board_cell(Some(index));
+                    place_new_window(space, Some(&output), &window, false);
+                }
+                constrain_tiled_toplevel(toplevel);
                 toplevel.send_configure();
             }
         }
@@ -391,104 +496,84 @@ fn ensure_initial_configure(surface: &WlSurface, space: &Space<WindowElement>, p
     };
 }
 
+/// Places a window into the next free cell of the output's board.
+///
+/// Windows already on that board keep their cell, floating windows don't get one.
 fn place_new_window(
     space: &mut Space<WindowElement>,
-    pointer_location: Point<f64, Logical>,
+    output: Option<&Output>,
     window: &WindowElement,
     activate: bool,
 ) {
+    if window.is_floating() {
+        place_floating(space, output, window, activate);
+        return;
+    }
+
     // place the window in a chessboard layout
     // first window is centered, others are placed around it
-    use std::collections::HashMap;
-    use std::sync::atomic::{AtomicUsize, Ordering};
-    use once_cell::sync::Lazy;
-
-    // Keep track of window count and positions
-    static WINDOW_COUNT: Lazy<AtomicUsize> = Lazy::new(|| AtomicUsize::new(0));
-    static POSITIONS: Lazy<std::sync::Mutex<HashMap<usize, (i32, i32)>>> = 
-        Lazy::new(|| std::sync::Mutex::new(HashMap::new()));
-
-    let output = space
-        .output_under(pointer_location)
-        .next()
-        .or_else(|| space.outputs().next())
-        .cloned();
-    let output_geometry = output
-        .and_then(|o| {
-            let geo = space.output_geometry(&o)?;
-            let map = layer_map_for_output(&o);
-            let zone = map.non_exclusive_zone();
-            Some(Rectangle::new(geo.loc + zone.loc, zone.size))
-        })
-        .unwrap_or_else(|| Rectangle::from_size((800, 800).into()));
+    let layout = crate::config::layout();
+    let rules = window.rule_properties();
+    let rule_output = rules
+        .output
+        .as_deref()
+        .and_then(|name| output_by_name(space, name));
+    // windows mapped for the first time go back to where they were before a restart,
+    // unless their rules put them somewhere else; restored minimized windows were on a board before
+    let first_map = space.element_location(window).is_none() && window.board_identity().is_none();
+    let restored = (first_map && rule_output.is_none() && rules.cell.is_none())
+        .then(|| take_saved_cell(space, window))
+        .flatten();
+    let output = match restored.as_ref() {
+        Some((output, _)) => Some(output.clone()),
+        None => rule_output.or_else(|| output.cloned()),
+    };
+    let Some(output) = output.or_else(|| space.outputs().next().cloned()) else {
+        // fixup_positions puts the window on a board once there is an output
+        window.set_board_cell(None);
+        space.map_element(window.clone(), (0, 0), activate);
+        return;
+    };
+    let board = board_geometry(space, &output);
 
     // set the initial toplevel bounds
     #[allow(irrefutable_let_patterns)]
     if let Some(toplevel) = window.0.toplevel() {
         toplevel.with_pending_state(|state| {
-            state.bounds = Some(output_geometry.size);
+            state.bounds = Some(board.size);
         });
     }
 
-    // Get window count (including this new window)
-    let window_count = WINDOW_COUNT.fetch_add(1, Ordering::SeqCst) + 1;
-    
-    // Calculate window position based on count
-    let (x, y) = if window_count == 1 {
-        // First window is centered
-        let center_x = output_geometry.loc.x + (output_geometry.size.w / 2);
-        let center_y = output_geometry.loc.y + (output_geometry.size.h / 2);
-        (center_x, center_y)
-    } else {
-        // Calculate position in a chessboard pattern around the center
-        // Define the grid size and spacing
-        let grid_size = (f64::sqrt(window_count as f64).ceil() as i32).max(3);
-        let cell_width = output_geometry.size.w / grid_size;
-        let cell_height = output_geometry.size.h / grid_size;
-        
-        // Calculate the center of the grid
-        let center_x = output_geometry.loc.x + (output_geometry.size.w / 2);
-        let center_y = output_geometry.loc.y + (output_geometry.size.h / 2);
-        
-        // Calculate the position in the grid (spiral pattern from center)
-        let mut positions = POSITIONS.lock().unwrap();
-        
-        // Check if we already have a position for this window index
-        if let Some(pos) = positions.get(&window_count) {
-            *pos
-        } else {
-            // Generate a new position using a spiral pattern
-            let layer = ((window_count as f64 - 1.0) / 8.0).floor() as i32 + 1;
-            let position_in_layer = (window_count - 1) % 8;
-            
-            let (dx, dy) = match position_in_layer {
-                0 => (layer, 0),      // right
-                1 => (layer, layer),   // bottom-right
-                2 => (0, layer),       // bottom
-                3 => (-layer, layer),  // bottom-left
-                4 => (-layer, 0),      // left
-                5 => (-layer, -layer), // top-left
-                6 => (0, -layer),      // top
-                7 => (layer, -layer),  // top-right
-                _ => unreachable!(),
-            };
-            
-            let x = center_x + dx * cell_width;
-            let y = center_y + dy * cell_height;
-            
-            // Store the position for future reference
-            let pos = (x, y);
-            positions.insert(window_count, pos);
-            pos
-        }
+    let previous_dimension = grid_dimension(board_windows(space, &output));
+    let index = match (restored, window.board_cell()) {
+        (Some((_, index)), _) => index,
+        (None, Some(index)) if window.is_on_board(&output) => index,
+        _ => rules
+            .cell
+            .filter(|cell| {
+                !is_cell_reserved(&output, *cell)
+                    && !board_windows(space, &output).any(|w| w != window && w.board_cell() == Some(*cell))
+            })
+            .unwrap_or_else(|| next_cell(space, &output)),
     };
+    window.set_board_output(Some(&output));
+    window.set_board_cell(Some(index));
+    let dimension = grid_dimension(board_windows(space, &output).chain(std::iter::once(window)));
+
+    place_in_cell(
+        space,
+        window,
+        cell_geometry(board, dimension, index),
+        layout.tiled_sizing,
+        activate,
+    );
 
-    // Adjust position to ensure the window is centered at the calculated position
-    let window_size = window.bbox().size;
-    let adjusted_x = x - (window_size.w / 2);
-    let adjusted_y = y - (window_size.h / 2);
+    // the cells shrink when the grid grows, tiled windows need to follow
+    if layout.tiled_sizing && dimension != previous_dimension {
+        arrange_board(space, &output, true);
+    }
 
-    space.map_element(window.clone(), (adjusted_x, adjusted_y), activate);
+    save_layout();
 }
 
 pub fn fixup_positions(space: &mut Space<WindowElement>, pointer_location: Point<f64, Logical>) {
@@ -499,11 +584,60 @@ pub fn fixup_positions(space: &mut Space<WindowElement>, pointer_location: Point
             .output_geometry(&output)
             .map(|geo| geo.size)
             .unwrap_or_else(|| Size::from((0, 0)));
-        space.map_output(&output, offset);
+        map_output_home(space, &output, offset);
         layer_map_for_output(&output).arrange();
         offset.x += size.w;
     }
 
+    // a monitor plugged into another connector keeps its board
+    for window in space.elements() {
+        if window.board_output(space).is_none() {
+            if let Some(output) = window.board_identity().and_then(|identity| identity.find(space)) {
+                window.set_board_output(Some(&output));
+            }
+        }
+    }
+
+    // windows go back to the board they had to leave once its output is back
+    let returning = space
+        .elements()
+        .filter_map(|window| {
+            let (identity, index) = window.board_origin()?;
+            Some((window.clone(), identity.find(space)?, index))
+        })
+        .collect::<Vec<_>>();
+    for (window, output, index) in returning {
+        window.clear_board_origin();
+        let occupied = board_windows(space, &output).any(|w| w != &window && w.board_cell() == Some(index));
+        let index = if occupied {
+            next_cell(space, &output)
+        } else {
+            index
+        };
+        window.set_board_output(Some(&output));
+        window.set_board_cell(Some(index));
+        place_new_window(space, Some(&output), &window, false);
+    }
+
+    // windows on the board of an output that went away join the board under the pointer
+    let target = output_at(space, pointer_location);
+    let migrating = space
+        .elements()
+        .filter(|window| window.board_cell().is_some() && window.board_output(space).is_none())
+        .cloned()
+        .collect::<Vec<_>>();
+    for window in migrating {
+        window.remember_board_origin();
+        window.set_board_cell(None);
+        place_new_window(space, target.as_ref(), &window, false);
+    }
+
+    // outputs might have moved or changed their size, boards follow them
+    let tiled_sizing = crate::config::layout().tiled_sizing;
+    for output in space.outputs().cloned().collect::<Vec<_>>() {
+        arrange_board(space, &output, tiled_sizing);
+    }
+
     // fixup windows
     let mut orphaned_windows = Vec::new();
     let outputs = space
@@ -515,7 +649,7 @@ pub fn fixup_positions(space: &mut Space<WindowElement>, pointer_location: Point
             Some(Rectangle::new(geo.loc + zone.loc, zone.size))
         })
         .collect::<Vec<_>>();
-    for window in space.elements() {
+    for window in space.elements().filter(|window| window.board_cell().is_none()) {
         let window_location = match space.element_location(window) {
             Some(loc) => loc,
             None => continue,
@@ -527,6 +661,6 @@ pub fn fixup_positions(space: &mut Space<WindowElement>, pointer_location: Point
         }
     }
     for window in orphaned_windows.into_iter() {
-        place_new_window(space, pointer_location, &window, false);
+        place_new_window(space, target.as_ref(), &window, false);
     }
 }
@@ -0,0 +1,286 @@
+use std::cell::Cell;
+
+use smithay::{
+    input::pointer::MotionEvent,
+    output::Output,
+    utils::{Logical, Point, Rectangle, SERIAL_COUNTER},
+};
+
+use crate::{
+    config::{CenterRefill, Direction},
+    focus::KeyboardFocusTarget,
+    state::{AnvilState, Backend},
+};
+
+use super::{
+    arrange_board, board_windows, cell_index, cell_offset, is_cell_reserved, save_layout, WindowElement,
+};
+
+fn center(rect: Rectangle<i32, Logical>) -> Point<f64, Logical> {
+    rect.loc.to_f64() + rect.size.to_f64().downscale(2.0).to_point()
+}
+
+/// Distance along and across the direction from `from` to `to`
+fn directional_distance(
+    direction: Direction,
+    from: Point<f64, Logical>,
+    to: Point<f64, Logical>,
+) -> (f64, f64) {
+    let (dx, dy) = direction.delta();
+    let delta = to - from;
+    let along = delta.x * dx as f64 + delta.y * dy as f64;
+    let across = (delta.x * dy as f64).abs() + (delta.y * dx as f64).abs();
+    (along, across)
+}
+
+/// Nearest window in the given direction, wrapping around to the far side
+/// when there is none.
+///
+/// Windows are compared by their centers, windows straight ahead are preferred
+/// over closer ones off to the side.
+pub fn window_in_direction<'a>(
+    windows: impl Iterator<Item = (&'a WindowElement, Rectangle<i32, Logical>)> + Clone,
+    from: Rectangle<i32, Logical>,
+    direction: Direction,
+) -> Option<&'a WindowElement> {
+    let origin = center(from);
+    let score = |along: f64, across: f64| along + across * 2.0;
+
+    let ahead = windows
+        .clone()
+        .map(|(window, geometry)| (window, directional_distance(direction, origin, center(geometry))))
+        .filter(|(_, (along, _))| *along > 0.0)
+        .min_by(|(_, (a_along, a_across)), (_, (b_along, b_across))| {
+            score(*a_along, *a_across).total_cmp(&score(*b_along, *b_across))
+        })
+        .map(|(window, _)| window);
+
+    // wrap around: the window farthest away in the opposite direction
+    ahead.or_else(|| {
+        windows
+            .map(|(window, geometry)| (window, directional_distance(direction, origin, center(geometry))))
+            .filter(|(_, (along, across))| *along < 0.0 || *across > 0.0)
+            .min_by(|(_, (a_along, a_across)), (_, (b_along, b_across))| {
+                score(*a_along, *a_across).total_cmp(&score(*b_along, *b_across))
+            })
+            .map(|(window, _)| window)
+    })
+}
+
+/// Whether the window got the keyboard focus with the last focus change
+#[derive(Debug, Default)]
+struct Focused(Cell<bool>);
+
+impl WindowElement {
+    /// Whether the window holds the keyboard focus, without looking up the focused window
+    pub fn is_focused(&self) -> bool {
+        self.user_data()
+            .get::<Focused>()
+            .is_some_and(|focused| focused.0.get())
+    }
+}
+
+impl<BackendData: Backend> AnvilState<BackendData> {
+    /// Records which window holds the keyboard focus, none if the focus isn't on a window
+    pub fn mark_focused(&self, focused: Option<&WindowElement>) {
+        for window in self.space.elements().chain(self.minimized.iter()) {
+            window.user_data().insert_if_missing(Focused::default);
+            window
+                .user_data()
+                .get::<Focused>()
+                .unwrap()
+                .0
+                .set(Some(window) == focused);
+        }
+    }
+
+    /// Window currently holding the keyboard focus
+    pub fn focused_window(&self) -> Option<WindowElement> {
+        let focus = self.seat.get_keyboard()?.current_focus()?;
+        let KeyboardFocusTarget::Window(focused) = focus else {
+            return None;
+        };
+        self.space.elements().find(|window| window.0 == focused).cloned()
+    }
+
+    /// Moves the keyboard focus to the nearest window in the given direction
+    pub fn focus_direction(&mut self, direction: Direction) {
+        let from = match self.focused_window() {
+            Some(window) => self.space.element_geometry(&window),
+            None => {
+                // start from the pointer if nothing is focused
+                let location = self.pointer.current_location().to_i32_round();
+                Some(Rectangle::new(location, (0, 0).into()))
+            }
+        };
+        let Some(from) = from else {
+            return;
+        };
+
+        let focused = self.focused_window();
+        let candidates = self
+            .space
+            .elements()
+            .filter(|window| Some(*window) != focused.as_ref() && window.accepts_focus())
+            .filter_map(|window| Some((window, self.space.element_geometry(window)?)))
+            .collect::<Vec<_>>();
+        let Some(target) = window_in_direction(candidates.iter().copied(), from, direction).cloned() else {
+            return;
+        };
+
+        self.focus_window(&target);
+        let delta = self.reveal_window(&target);
+        self.warp_pointer_to(&target, delta);
+    }
+
+    /// Warps the pointer onto the window, so the pointer focus matches the keyboard focus.
+    ///
+    /// `pan` is the distance the viewport is about to scroll, the pointer is moved
+    /// along with it and ends up on the window once the scrolling is done.
+    pub fn warp_pointer_to(&mut self, window: &WindowElement, pan: Point<i32, Logical>) {
+        let Some(geometry) = self.space.element_geometry(window) else {
+            return;
+        };
+        let location = center(geometry) - pan.to_f64();
+
+        let pointer = self.pointer.clone();
+        let under = self.surface_under(location);
+        pointer.motion(
+            self,
+            under,
+            &MotionEvent {
+                location,
+                serial: SERIAL_COUNTER.next_serial(),
+                time: self.clock.now().as_millis(),
+            },
+        );
+        pointer.frame(self);
+    }
+
+    /// Window occupying the given cell of the output's board
+    pub fn window_in_cell(&self, output: &Output, index: usize) -> Option<WindowElement> {
+        board_windows(&self.space, output)
+            .find(|window| window.board_cell() == Some(index))
+            .cloned()
+    }
+
+    /// Exchanges the windows of two cells, either of them may be empty.
+    ///
+    /// The cells may be on the boards of different outputs. Cells kept free for a minimized,
+    /// maximized or fullscreen window count as occupied and are left alone, their window
+    /// takes them back later.
+    pub fn swap_cells(&mut self, source: (&Output, usize), target: (&Output, usize)) {
+        if is_cell_reserved(source.0, source.1) || is_cell_reserved(target.0, target.1) {
+            return;
+        }
+        let source_window = self.window_in_cell(source.0, source.1);
+        let target_window = self.window_in_cell(target.0, target.1);
+        if let Some(window) = source_window.as_ref() {
+            window.set_board_output(Some(target.0));
+            window.set_board_cell(Some(target.1));
+        }
+        if let Some(window) = target_window.as_ref() {
+            window.set_board_output(Some(source.0));
+            window.set_board_cell(Some(source.1));
+        }
+        if source.0 != target.0 {
+            // moved to another board on purpose, don't send them back after a hotplug
+            for window in source_window.iter().chain(target_window.iter()) {
+                window.clear_board_origin();
+            }
+        }
+
+        let tiled_sizing = crate::config::layout().tiled_sizing;
+        arrange_board(&mut self.space, source.0, tiled_sizing);
+        if source.0 != target.0 {
+            arrange_board(&mut self.space, target.0, tiled_sizing);
+        }
+        save_layout();
+    }
+
+    /// Board output and cell of the focused window
+    fn focused_cell(&self) -> Option<(WindowElement, Output, usize)> {
+        let window = self.focused_window()?;
+        let output = window.board_output(&self.space)?;
+        let index = window.board_cell()?;
+        Some((window, output, index))
+    }
+
+    /// Moves the focused window into the neighbouring cell, exchanging it
+    /// with the window there unless `only_empty` is set.
+    pub fn swap_direction(&mut self, direction: Direction, only_empty: bool) {
+        let Some((window, output, index)) = self.focused_cell() else {
+            return;
+        };
+
+        let (dx, dy) = direction.delta();
+        let target = cell_index(cell_offset(index) + Point::from((dx, dy)));
+        if only_empty && self.window_in_cell(&output, target).is_some() {
+            return;
+        }
+        self.swap_cells((&output, index), (&output, target));
+        self.follow_moved_window(&window);
+    }
+
+    /// Swaps the focused window into the center cell of its board
+    pub fn promote(&mut self) {
+        let Some((window, output, index)) = self.focused_cell() else {
+            return;
+        };
+        if index == 0 {
+            return;
+        }
+        self.swap_cells((&output, index), (&output, 0));
+        self.follow_moved_window(&window);
+    }
+
+    /// Removes a window that went away from its board and refills the center
+    /// cell according to the configured policy
+    pub fn window_unmapped(&mut self, window: &WindowElement) {
+        self.forget_minimized(window);
+        self.forget_unmaximized(window);
+        self.forget_fullscreen(window);
+        self.scratchpads.retain(|_, scratchpad| scratchpad != window);
+        self.forget_switcher_window(window);
+        if self.hovered_window.as_ref() == Some(window) {
+            self.hovered_window = None;
+        }
+        self.space.unmap_elem(window);
+        self.focus_stack.retain(|w| w != window);
+        save_layout();
+        self.update_idle_inhibit();
+
+        if let Some((output, index)) = window.board_output(&self.space).zip(window.board_cell()) {
+            self.cell_released(&output, index);
+        }
+    }
+
+    /// Refills the center cell of the board once its window left it, according to the
+    /// configured policy
+    pub fn cell_released(&mut self, output: &Output, index: usize) {
+        if index != 0 || self.window_in_cell(output, 0).is_some() {
+            return;
+        }
+
+        let candidate = match crate::config::layout().center_refill {
+            CenterRefill::Recent => self
+                .focus_stack
+                .iter()
+                .find(|w| w.is_on_board(output) && self.space.elements().any(|e| e == *w))
+                .cloned(),
+            CenterRefill::Spiral => board_windows(&self.space, output)
+                .min_by_key(|w| w.board_cell())
+                .cloned(),
+            CenterRefill::None => None,
+        };
+        if let Some(index) = candidate.and_then(|w| w.board_cell()) {
+            self.swap_cells((output, index), (output, 0));
+        }
+    }
+
+    /// Keeps the moved window in view with the pointer on it
+    fn follow_moved_window(&mut self, window: &WindowElement) {
+        let delta = self.reveal_window(window);
+        self.warp_pointer_to(window, delta);
+    }
+}
@@ -0,0 +1,55 @@
+use std::cell::Cell;
+
+use crate::state::{AnvilState, Backend};
+
+use super::WindowElement;
+
+/// Lowest opacity the `opacity` action takes a window down to, so it never disappears
+const MIN_OPACITY: f32 = 0.1;
+
+/// Opacity set with the `opacity` action, it takes precedence over the window rules
+#[derive(Debug, Default)]
+struct OpacityOverride(Cell<Option<f32>>);
+
+impl WindowElement {
+    /// Opacity set with the `opacity` action or by the window rules, fully opaque otherwise
+    pub fn base_opacity(&self) -> f32 {
+        self.user_data()
+            .get::<OpacityOverride>()
+            .and_then(|opacity| opacity.0.get())
+            .or(self.rule_properties().opacity)
+            .unwrap_or(1.0)
+            .clamp(0.0, 1.0)
+    }
+
+    /// Opacity the window is drawn with, unfocused windows are dimmed by `effects.inactive_dim`
+    pub fn opacity(&self) -> f32 {
+        let opacity = self.base_opacity();
+        if self.is_focused() {
+            opacity
+        } else {
+            opacity * crate::config::effects().inactive_dim.clamp(0.0, 1.0)
+        }
+    }
+}
+
+impl<BackendData: Backend> AnvilState<BackendData> {
+    /// Makes the focused window more opaque (or more transparent for a negative `delta`)
+    pub fn change_opacity(&mut self, delta: f32) {
+        let Some(window) = self.focused_window() else {
+            return;
+        };
+        let opacity = (window.base_opacity() + delta).clamp(MIN_OPACITY, 1.0);
+        window.user_data().insert_if_missing(OpacityOverride::default);
+        window
+            .user_data()
+            .get::<OpacityOverride>()
+            .unwrap()
+            .0
+            .set(Some(opacity));
+        // nothing of the window itself changed, redraw its outputs with the new opacity
+        for output in self.space.outputs_for_element(&window) {
+            self.backend_data.reset_buffers(&output);
+        }
+    }
+}
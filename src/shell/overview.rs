@@ -0,0 +1,386 @@
+use std::{cell::RefCell, collections::HashMap};
+
+use smithay::{
+    backend::renderer::{element::solid::SolidColorBuffer, Color32F},
+    desktop::Space,
+    input::pointer::MotionEvent,
+    output::Output,
+    utils::{Logical, Point, Rectangle, Size, SERIAL_COUNTER},
+};
+
+use crate::{
+    config::Direction,
+    state::{AnvilState, Backend},
+};
+
+use super::{
+    board_geometry, board_windows, cell_geometry, cell_index, cell_offset, grid_dimension, output_at,
+    WindowElement,
+};
+
+const CELL_COLOR: Color32F = Color32F::new(0.3, 0.3, 0.35, 1.0);
+const SELECTED_COLOR: Color32F = Color32F::new(0.45, 0.55, 0.85, 1.0);
+const DRAG_SOURCE_COLOR: Color32F = Color32F::new(0.85, 0.6, 0.3, 1.0);
+const MINIMIZED_COLOR: Color32F = Color32F::new(0.22, 0.22, 0.26, 1.0);
+
+/// Share of the output the scaled board may cover
+const OVERVIEW_FILL: f64 = 0.9;
+/// Gap between two cells of the overview
+const CELL_PADDING: i32 = 6;
+/// Share of the output height taken by the row of minimized windows
+const SHELF_FILL: f64 = 0.15;
+
+/// Interactive overview of the whole board
+#[derive(Debug, Default)]
+pub struct Overview {
+    /// Cell under the pointer or picked with the keyboard
+    pub selected: Option<(Output, usize)>,
+    /// Cell a window is being dragged from
+    pub drag_source: Option<(Output, usize)>,
+    /// Minimized windows, shown in a row below the board
+    pub minimized: Vec<WindowElement>,
+    /// Slot of the minimized row under the pointer or picked with the keyboard
+    pub picked: Option<(Output, usize)>,
+    backgrounds: RefCell<HashMap<(String, usize), SolidColorBuffer>>,
+    slot_backgrounds: RefCell<HashMap<(String, usize), SolidColorBuffer>>,
+}
+
+impl Overview {
+    /// Background of the given cell, sized and colored for its current state
+    pub fn cell_background(
+        &self,
+        output: &Output,
+        index: usize,
+        size: Size<i32, Logical>,
+    ) -> SolidColorBuffer {
+        let is = |cell: &Option<(Output, usize)>| {
+            cell.as_ref()
+                .is_some_and(|(cell_output, cell_index)| cell_output == output && *cell_index == index)
+        };
+        let color = if is(&self.drag_source) {
+            DRAG_SOURCE_COLOR
+        } else if is(&self.selected) {
+            SELECTED_COLOR
+        } else {
+            CELL_COLOR
+        };
+        let mut backgrounds = self.backgrounds.borrow_mut();
+        let buffer = backgrounds
+            .entry((output.name(), index))
+            .or_insert_with(|| SolidColorBuffer::new(size, color));
+        buffer.update(size, color);
+        buffer.clone()
+    }
+
+    /// Background of the given slot of the minimized row
+    pub fn slot_background(
+        &self,
+        output: &Output,
+        slot: usize,
+        size: Size<i32, Logical>,
+    ) -> SolidColorBuffer {
+        let picked = self
+            .picked
+            .as_ref()
+            .is_some_and(|(picked_output, picked_slot)| picked_output == output && *picked_slot == slot);
+        let color = if picked { SELECTED_COLOR } else { MINIMIZED_COLOR };
+        let mut backgrounds = self.slot_backgrounds.borrow_mut();
+        let buffer = backgrounds
+            .entry((output.name(), slot))
+            .or_insert_with(|| SolidColorBuffer::new(size, color));
+        buffer.update(size, color);
+        buffer.clone()
+    }
+}
+
+/// Placement of the scaled down board on an output
+#[derive(Debug, Clone, Copy)]
+pub struct OverviewLayout {
+    board: Rectangle<i32, Logical>,
+    dimension: i32,
+    /// Number of rings around the center cell shown
+    pub rings: i32,
+    scale: f64,
+    output_center: Point<f64, Logical>,
+    /// Area of the row of minimized windows, empty without any
+    shelf: Rectangle<i32, Logical>,
+    /// Number of minimized windows
+    pub slots: usize,
+}
+
+impl OverviewLayout {
+    /// Layout of the overview on the output, with a row below the board for the given number
+    /// of minimized windows
+    pub fn for_output(space: &Space<WindowElement>, output: &Output, minimized: usize) -> Option<Self> {
+        let mut output_geometry = space.output_geometry(output)?;
+        let shelf_height = if minimized > 0 {
+            (output_geometry.size.h as f64 * SHELF_FILL).round() as i32
+        } else {
+            0
+        };
+        output_geometry.size.h -= shelf_height;
+        let shelf = Rectangle::new(
+            (0, output_geometry.size.h).into(),
+            (output_geometry.size.w, shelf_height).into(),
+        );
+        let board = board_geometry(space, output);
+        let dimension = grid_dimension(board_windows(space, output));
+        let rings = board_windows(space, output)
+            .filter_map(WindowElement::board_cell)
+            .map(|index| {
+                let offset = cell_offset(index);
+                offset.x.abs().max(offset.y.abs())
+            })
+            .max()
+            .unwrap_or(0)
+            .max(1);
+
+        let cells = (2 * rings + 1) as f64;
+        let extent = Size::<f64, Logical>::from((
+            (board.size.w / dimension) as f64 * cells,
+            (board.size.h / dimension) as f64 * cells,
+        ));
+        let scale = f64::min(
+            output_geometry.size.w as f64 / extent.w,
+            output_geometry.size.h as f64 / extent.h,
+        ) * OVERVIEW_FILL;
+
+        Some(OverviewLayout {
+            board,
+            dimension,
+            rings,
+            scale,
+            output_center: output_geometry.size.to_f64().downscale(2.0).to_point(),
+            shelf,
+            slots: minimized,
+        })
+    }
+
+    /// Indices of all cells shown
+    pub fn cells(&self) -> impl Iterator<Item = usize> {
+        0..((2 * self.rings + 1) * (2 * self.rings + 1)) as usize
+    }
+
+    /// Area of the cell in output local coordinates
+    pub fn cell_rect(&self, index: usize) -> Rectangle<i32, Logical> {
+        let cell = cell_geometry(self.board, self.dimension, index).to_f64();
+        let board_center = self.board.to_f64().loc + self.board.size.to_f64().downscale(2.0).to_point();
+        let loc = self.output_center + (cell.loc - board_center).upscale(self.scale);
+        let size: Size<i32, Logical> = cell.size.upscale(self.scale).to_i32_round();
+        Rectangle::new(
+            loc.to_i32_round() + Point::from((CELL_PADDING, CELL_PADDING)),
+            padded(size),
+        )
+    }
+
+    /// Cell at the given output local location
+    pub fn cell_at(&self, location: Point<f64, Logical>) -> Option<usize> {
+        self.cells()
+            .find(|index| self.cell_rect(*index).to_f64().contains(location))
+    }
+
+    /// Area of a slot of the minimized row in output local coordinates,
+    /// the slots are centered below the board
+    pub fn slot_rect(&self, slot: usize) -> Rectangle<i32, Logical> {
+        let height = self.shelf.size.h;
+        let width = (height * 3 / 2).min(self.shelf.size.w / self.slots.max(1) as i32);
+        let start = self.shelf.loc.x + (self.shelf.size.w - width * self.slots as i32) / 2;
+        Rectangle::new(
+            Point::from((
+                start + width * slot as i32 + CELL_PADDING,
+                self.shelf.loc.y + CELL_PADDING,
+            )),
+            padded(Size::from((width, height))),
+        )
+    }
+
+    /// Slot of the minimized row at the given output local location
+    pub fn slot_at(&self, location: Point<f64, Logical>) -> Option<usize> {
+        (0..self.slots).find(|slot| self.slot_rect(*slot).to_f64().contains(location))
+    }
+}
+
+impl<BackendData: Backend> AnvilState<BackendData> {
+    pub fn toggle_overview(&mut self) {
+        if self.overview.take().is_some() {
+            self.refocus_pointer();
+            return;
+        }
+
+        let focused = self.focused_window().and_then(|window| {
+            let output = window.board_output(&self.space)?;
+            Some((output, window.board_cell()?))
+        });
+        let selected = focused.or_else(|| {
+            let output = output_at(&self.space, self.pointer.current_location())?;
+            Some((output, 0))
+        });
+        self.overview = Some(Overview {
+            selected,
+            minimized: self.minimized.clone(),
+            ..Default::default()
+        });
+        self.overview_motion(self.pointer.current_location());
+        self.refocus_pointer();
+    }
+
+    /// Moves the pointer focus onto or off the windows after the overview opened or closed,
+    /// while it is shown the overview covers all outputs and no client has the pointer
+    fn refocus_pointer(&mut self) {
+        let location = self.pointer.current_location();
+        let under = self.surface_under(location);
+        let pointer = self.pointer.clone();
+        pointer.motion(
+            self,
+            under,
+            &MotionEvent {
+                location,
+                serial: SERIAL_COUNTER.next_serial(),
+                time: self.clock.now().as_millis(),
+            },
+        );
+        pointer.frame(self);
+    }
+
+    /// Highlights the cell under the pointer
+    pub fn overview_motion(&mut self, location: Point<f64, Logical>) {
+        let Some(output) = self.space.output_under(location).next().cloned() else {
+            return;
+        };
+        let Some(layout) = OverviewLayout::for_output(&self.space, &output, self.minimized.len()) else {
+            return;
+        };
+        let Some(overview) = self.overview.as_mut() else {
+            return;
+        };
+        let output_location = self.space.output_geometry(&output).unwrap().loc.to_f64();
+        if let Some(cell) = layout.cell_at(location - output_location) {
+            overview.selected = Some((output, cell));
+            overview.picked = None;
+        } else if let Some(slot) = layout.slot_at(location - output_location) {
+            overview.picked = Some((output, slot));
+            overview.selected = None;
+        }
+    }
+
+    /// Left button picks up and drops windows, clicking a window selects it
+    pub fn overview_button(&mut self, pressed: bool) {
+        let Some(selected) = self.overview.as_ref().map(|overview| overview.selected.clone()) else {
+            return;
+        };
+
+        if pressed {
+            let source = selected.filter(|(output, index)| self.window_in_cell(output, *index).is_some());
+            self.overview.as_mut().unwrap().drag_source = source;
+            return;
+        }
+
+        let source = self.overview.as_mut().unwrap().drag_source.take();
+        match (source, selected) {
+            (Some(source), Some(target)) if source != target => {
+                self.swap_cells((&source.0, source.1), (&target.0, target.1))
+            }
+            (Some(_), Some(_)) => self.overview_select(),
+            // clicking a minimized window restores it
+            (None, None) => self.overview_select(),
+            _ => {}
+        }
+    }
+
+    /// Moves the keyboard selection to the neighbouring cell, or between the board and the row
+    /// of minimized windows
+    pub fn overview_move(&mut self, direction: Direction) {
+        let minimized = self.minimized.len();
+        if let Some((output, slot)) = self
+            .overview
+            .as_ref()
+            .and_then(|overview| overview.picked.clone())
+        {
+            let Some(layout) = OverviewLayout::for_output(&self.space, &output, minimized) else {
+                return;
+            };
+            let overview = self.overview.as_mut().unwrap();
+            match direction {
+                Direction::Left if slot > 0 => overview.picked = Some((output, slot - 1)),
+                Direction::Right if slot + 1 < minimized => overview.picked = Some((output, slot + 1)),
+                Direction::Up => {
+                    overview.picked = None;
+                    overview.selected = Some((output, cell_index(Point::from((0, layout.rings)))));
+                }
+                _ => {}
+            }
+            return;
+        }
+
+        let Some((output, index)) = self
+            .overview
+            .as_ref()
+            .and_then(|overview| overview.selected.clone())
+            .or_else(|| Some((output_at(&self.space, self.pointer.current_location())?, 0)))
+        else {
+            return;
+        };
+        let Some(layout) = OverviewLayout::for_output(&self.space, &output, minimized) else {
+            return;
+        };
+        let Some(overview) = self.overview.as_mut() else {
+            return;
+        };
+
+        let (dx, dy) = direction.delta();
+        let offset = cell_offset(index) + Point::from((dx, dy));
+        if offset.x.abs() <= layout.rings && offset.y.abs() <= layout.rings {
+            overview.selected = Some((output, cell_index(offset)));
+        } else if direction == Direction::Down && minimized > 0 {
+            // below the bottom row of the board
+            overview.selected = None;
+            overview.picked = Some((output, 0));
+        }
+    }
+
+    /// Focuses the window in the selected cell, or restores the picked minimized window,
+    /// and leaves the overview
+    pub fn overview_select(&mut self) {
+        if let Some(window) = self.overview.as_ref().and_then(|overview| {
+            let (_, slot) = overview.picked.as_ref()?;
+            overview.minimized.get(*slot).cloned()
+        }) {
+            self.overview = None;
+            self.refocus_pointer();
+            self.restore_window(&window);
+            return;
+        }
+
+        let Some(window) = self
+            .overview
+            .as_ref()
+            .and_then(|overview| overview.selected.as_ref())
+            .and_then(|(output, index)| self.window_in_cell(output, *index))
+        else {
+            return;
+        };
+
+        self.overview = None;
+        self.refocus_pointer();
+        self.focus_window(&window);
+        self.reveal_window(&window);
+    }
+
+    /// Keeps the minimized windows shown by the overview up to date
+    pub fn sync_overview_minimized(&mut self) {
+        let minimized = self.minimized.clone();
+        if let Some(overview) = self.overview.as_mut() {
+            overview.picked = overview.picked.take().filter(|(_, slot)| *slot < minimized.len());
+            overview.minimized = minimized;
+        }
+    }
+}
+
+/// Size left inside the padding of a cell or slot, at least a pixel for
+/// cells smaller than the padding on a crowded board
+fn padded(size: Size<i32, Logical>) -> Size<i32, Logical> {
+    Size::from((
+        (size.w - CELL_PADDING * 2).max(1),
+        (size.h - CELL_PADDING * 2).max(1),
+    ))
+}
@@ -0,0 +1,120 @@
+use std::cell::RefCell;
+
+use smithay::{
+    desktop::Space,
+    output::Output,
+    utils::{Logical, Point, Size},
+};
+
+use super::{
+    arrange_board, board_windows, map_with_transients, place_new_window, reserve_cell, unreserve_cell,
+    OutputIdentity, WindowElement,
+};
+
+/// Placement of a window taken off the board for a while, e.g. to be maximized or fullscreened
+#[derive(Debug, Clone)]
+pub struct SavedPlacement {
+    pub location: Option<Point<i32, Logical>>,
+    pub size: Option<Size<i32, Logical>>,
+    /// Board output and cell, the cell is reserved until the placement is restored or released
+    cell: Option<(OutputIdentity, usize)>,
+}
+
+/// Board output and cell kept free for a window while its placement is saved
+#[derive(Debug, Default)]
+struct ReservedCell(RefCell<Option<(OutputIdentity, usize)>>);
+
+impl WindowElement {
+    fn reserved_cell(&self) -> &ReservedCell {
+        self.user_data().insert_if_missing(ReservedCell::default);
+        self.user_data().get::<ReservedCell>().unwrap()
+    }
+
+    /// Board output and cell the window occupies, or gets back once it leaves maximize or fullscreen
+    pub fn held_cell(&self) -> Option<(OutputIdentity, usize)> {
+        self.board_identity()
+            .zip(self.board_cell())
+            .or_else(|| self.reserved_cell().0.borrow().clone())
+    }
+}
+
+impl SavedPlacement {
+    /// Remembers where the window is and takes it off the board, its cell is kept free
+    pub fn save(
+        space: &Space<WindowElement>,
+        window: &WindowElement,
+        size: Option<Size<i32, Logical>>,
+    ) -> Self {
+        let placement = SavedPlacement {
+            location: space.element_location(window),
+            size,
+            cell: window.board_identity().zip(window.board_cell()),
+        };
+        if let Some((output, index)) = placement.board(space) {
+            reserve_cell(&output, index);
+        }
+        if placement.cell.is_some() {
+            *window.reserved_cell().0.borrow_mut() = placement.cell.clone();
+        }
+        // off the board, so rearranging it leaves the window alone
+        window.set_board_cell(None);
+        placement
+    }
+
+    fn board(&self, space: &Space<WindowElement>) -> Option<(Output, usize)> {
+        let (identity, index) = self.cell.as_ref()?;
+        Some((identity.find(space)?, *index))
+    }
+
+    /// Gives the window its cell back, unless another window was swapped into it meanwhile.
+    ///
+    /// Returns where the window was.
+    pub fn restore(
+        self,
+        space: &Space<WindowElement>,
+        window: &WindowElement,
+    ) -> Option<Point<i32, Logical>> {
+        if self.cell.is_some() {
+            window.reserved_cell().0.take();
+        }
+        if let Some((output, index)) = self.board(space) {
+            unreserve_cell(&output, index);
+            let taken = board_windows(space, &output).any(|w| w != window && w.board_cell() == Some(index));
+            if !taken {
+                window.set_board_output(Some(&output));
+                window.set_board_cell(Some(index));
+            }
+        }
+        self.location
+    }
+
+    /// Frees the reserved cell, the window went away
+    pub fn release(self, space: &Space<WindowElement>) {
+        if let Some((output, index)) = self.board(space) {
+            unreserve_cell(&output, index);
+        }
+    }
+}
+
+/// Puts a window whose placement was restored back into its cell, or where it floated before.
+///
+/// Windows whose cell is gone get a new one.
+pub fn return_to_placement(
+    space: &mut Space<WindowElement>,
+    window: &WindowElement,
+    location: Option<Point<i32, Logical>>,
+) {
+    let board = window.board_output(space);
+
+    let tiled_sizing = crate::config::layout().tiled_sizing;
+    match (location, board) {
+        (Some(_), Some(output)) if tiled_sizing && window.board_cell().is_some() => {
+            // the board may have changed meanwhile, fit the window to its cell again
+            arrange_board(space, &output, tiled_sizing);
+        }
+        (Some(location), _) if window.is_floating() || window.board_cell().is_some() => {
+            map_with_transients(space, window, location, false);
+        }
+        (_, board) => place_new_window(space, board.as_ref(), window, false),
+    }
+}
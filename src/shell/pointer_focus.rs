@@ -0,0 +1,149 @@
+use std::time::Duration;
+
+use smithay::{
+    desktop::layer_map_for_output,
+    reexports::calloop::timer::{TimeoutAction, Timer},
+    utils::{Logical, Point, Rectangle, SERIAL_COUNTER},
+    wayland::{
+        compositor::with_states,
+        shell::wlr_layer::{KeyboardInteractivity, Layer as WlrLayer, LayerSurfaceCachedState},
+    },
+};
+
+use crate::state::{AnvilState, Backend};
+
+use super::{pending_scroll, FullscreenSurface, WindowElement};
+
+impl<BackendData: Backend> AnvilState<BackendData> {
+    /// Whether a layer surface above the windows takes all keyboard input
+    pub fn exclusive_layer_focus(&self) -> bool {
+        self.layer_shell_state.layer_surfaces().any(|layer| {
+            let data = with_states(layer.wl_surface(), |states| {
+                *states.cached_state.get::<LayerSurfaceCachedState>().current()
+            });
+            data.keyboard_interactivity == KeyboardInteractivity::Exclusive
+                && (data.layer == WlrLayer::Top || data.layer == WlrLayer::Overlay)
+        })
+    }
+
+    /// Window the pointer is in, none while it is over a layer surface above the windows
+    fn window_under_pointer(&self, location: Point<f64, Logical>) -> Option<WindowElement> {
+        let output = self.space.output_under(location).next()?;
+        let output_geo = self.space.output_geometry(output)?;
+        if let Some(window) = output
+            .user_data()
+            .get::<FullscreenSurface>()
+            .and_then(|f| f.get())
+        {
+            return Some(window);
+        }
+
+        let layers = layer_map_for_output(output);
+        let location_on_output = location - output_geo.loc.to_f64();
+        if layers
+            .layer_under(WlrLayer::Overlay, location_on_output)
+            .is_some()
+            || layers.layer_under(WlrLayer::Top, location_on_output).is_some()
+        {
+            return None;
+        }
+
+        self.space
+            .element_under(location)
+            .map(|(window, _)| window.clone())
+    }
+
+    /// Focuses the window the pointer entered if `focus.follows_mouse` is set,
+    /// once the pointer stayed in it for `focus.follows_mouse_delay`
+    pub fn focus_follows_pointer(&mut self, location: Point<f64, Logical>) {
+        let config = crate::config::focus();
+        if !config.follows_mouse || self.overview.is_some() {
+            return;
+        }
+        let window = self.window_under_pointer(location);
+        if window == self.hovered_window {
+            return;
+        }
+        self.hovered_window = window.clone();
+        if let Some(token) = self.hover_focus_timer.take() {
+            self.handle.remove(token);
+        }
+        let Some(window) = window else {
+            return;
+        };
+
+        if config.follows_mouse_delay == 0 {
+            self.focus_hovered_window(&window);
+            return;
+        }
+        let timer = Timer::from_duration(Duration::from_millis(config.follows_mouse_delay));
+        self.hover_focus_timer = self
+            .handle
+            .insert_source(timer, move |_, _, state| {
+                state.hover_focus_timer = None;
+                if state.hovered_window.as_ref() == Some(&window) {
+                    state.focus_hovered_window(&window);
+                }
+                TimeoutAction::Drop
+            })
+            .ok();
+    }
+
+    /// Gives the window under the pointer keyboard focus without raising it
+    fn focus_hovered_window(&mut self, window: &WindowElement) {
+        let keyboard = self.seat.get_keyboard().unwrap();
+        // popup grabs and exclusive layer surfaces keep the focus
+        if self.pointer.is_grabbed()
+            || keyboard.is_grabbed()
+            || self.seat.get_touch().is_some_and(|touch| touch.is_grabbed())
+            || self.exclusive_layer_focus()
+        {
+            return;
+        }
+        if !window.accepts_focus()
+            || self.space.element_location(window).is_none()
+            || self.focused_window().as_ref() == Some(window)
+        {
+            return;
+        }
+        keyboard.set_focus(self, Some(window.clone().into()), SERIAL_COUNTER.next_serial());
+    }
+
+    /// Warps the pointer onto a window that got focus through the keyboard
+    /// if `focus.mouse_follows_focus` is set, unless the pointer is in it already
+    pub fn pointer_follows_focus(&mut self, previous: Option<WindowElement>) {
+        if !crate::config::focus().mouse_follows_focus || self.overview.is_some() {
+            return;
+        }
+        let Some(window) = self
+            .focused_window()
+            .filter(|focused| Some(focused) != previous.as_ref())
+        else {
+            return;
+        };
+        let Some(geometry) = self.space.element_geometry(&window) else {
+            return;
+        };
+
+        // the viewport may still be scrolling towards the window, the pointer is moved along with it
+        let center = geometry.loc + geometry.size.downscale(2).to_point();
+        let pan = self
+            .space
+            .outputs()
+            .map(|output| (output, pending_scroll(&self.space, output)))
+            .find(|(output, pan)| {
+                self.space
+                    .output_geometry(output)
+                    .is_some_and(|geo| Rectangle::new(geo.loc + *pan, geo.size).contains(center))
+            })
+            .map(|(_, pan)| pan)
+            .unwrap_or_default();
+        if geometry
+            .to_f64()
+            .contains(self.pointer.current_location() + pan.to_f64())
+        {
+            return;
+        }
+        self.warp_pointer_to(&window, pan);
+    }
+}
@@ -0,0 +1,207 @@
+use std::cell::RefCell;
+
+use smithay::{
+    desktop::WindowSurface,
+    reexports::wayland_protocols::xdg::decoration::zv1::server::zxdg_toplevel_decoration_v1::Mode,
+    utils::{Logical, Point, Size},
+    wayland::{compositor::with_states, shell::xdg::XdgToplevelSurfaceData},
+};
+
+use crate::{
+    config::{Pattern, StackingClass},
+    state::{AnvilState, Backend},
+};
+
+use super::{place_new_window, WindowElement};
+
+/// Properties the window rules give a window
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RuleProperties {
+    pub floating: Option<bool>,
+    pub size: Option<Size<i32, Logical>>,
+    pub position: Option<Point<i32, Logical>>,
+    pub output: Option<String>,
+    pub cell: Option<usize>,
+    pub ssd: Option<bool>,
+    pub fullscreen: Option<bool>,
+    pub fake_fullscreen: bool,
+    pub opacity: Option<f32>,
+    pub stacking: Option<StackingClass>,
+    pub inhibit_idle: bool,
+    pub no_focus: bool,
+    pub activation: Option<bool>,
+}
+
+impl RuleProperties {
+    /// Whether the window has to be placed again for the new properties to take effect
+    fn placement_differs(&self, other: &RuleProperties) -> bool {
+        self.floating != other.floating
+            || self.size != other.size
+            || self.position != other.position
+            || self.output != other.output
+            || self.cell != other.cell
+    }
+}
+
+/// Rule properties last applied to a window
+#[derive(Debug, Default)]
+struct AppliedRules(RefCell<RuleProperties>);
+
+impl WindowElement {
+    fn applied_rules(&self) -> &AppliedRules {
+        self.user_data().insert_if_missing(AppliedRules::default);
+        self.user_data().get::<AppliedRules>().unwrap()
+    }
+
+    pub fn rule_properties(&self) -> RuleProperties {
+        self.user_data()
+            .get::<AppliedRules>()
+            .map(|rules| rules.0.borrow().clone())
+            .unwrap_or_default()
+    }
+
+    pub fn inhibits_idle(&self) -> bool {
+        self.rule_properties().inhibit_idle
+    }
+
+    /// Whether the window may get keyboard focus
+    pub fn accepts_focus(&self) -> bool {
+        !self.rule_properties().no_focus
+    }
+}
+
+/// app_id and title of the window, WM_CLASS and WM_NAME for X11 windows.
+///
+/// Either is empty while the client didn't set it.
+pub fn window_identity(window: &WindowElement) -> (String, String) {
+    match window.0.underlying_surface() {
+        WindowSurface::Wayland(toplevel) => with_states(toplevel.wl_surface(), |states| {
+            let data = states
+                .data_map
+                .get::<XdgToplevelSurfaceData>()
+                .unwrap()
+                .lock()
+                .unwrap();
+            (
+                data.app_id.clone().unwrap_or_default(),
+                data.title.clone().unwrap_or_default(),
+            )
+        }),
+        #[cfg(feature = "xwayland")]
+        WindowSurface::X11(surface) => (surface.class(), surface.title()),
+    }
+}
+
+/// Whether the value matches the pattern, a rule without the pattern matches anything
+fn pattern_matches(pattern: Option<&Pattern>, value: &str) -> bool {
+    pattern.is_none_or(|pattern| pattern.is_match(value))
+}
+
+/// Merges the properties of all rules matching the window, later rules win
+pub fn evaluate_rules(window: &WindowElement) -> RuleProperties {
+    let (app_id, title) = window_identity(window);
+    let mut properties = RuleProperties::default();
+    crate::config::with_config(|config| {
+        for rule in &config.rules {
+            if !pattern_matches(rule.app_id.as_ref(), &app_id)
+                || !pattern_matches(rule.title.as_ref(), &title)
+            {
+                continue;
+            }
+            properties.floating = rule.floating.or(properties.floating);
+            properties.size = rule.size.map(|[w, h]| Size::from((w, h))).or(properties.size);
+            properties.position = rule
+                .position
+                .map(|[x, y]| Point::from((x, y)))
+                .or(properties.position);
+            properties.output = rule.output.clone().or(properties.output);
+            properties.cell = rule.cell.or(properties.cell);
+            properties.ssd = rule.ssd.or(properties.ssd);
+            properties.fullscreen = rule.fullscreen.or(properties.fullscreen);
+            properties.fake_fullscreen = rule.fake_fullscreen.unwrap_or(properties.fake_fullscreen);
+            properties.opacity = rule.opacity.or(properties.opacity);
+            properties.stacking = rule.stacking.or(properties.stacking);
+            properties.inhibit_idle = rule.inhibit_idle.unwrap_or(properties.inhibit_idle);
+            properties.no_focus = rule.no_focus.unwrap_or(properties.no_focus);
+            properties.activation = rule.activation.or(properties.activation);
+        }
+    });
+    properties
+}
+
+impl<BackendData: Backend> AnvilState<BackendData> {
+    /// Evaluates the window rules for the window and applies what changed since the last time.
+    ///
+    /// Windows not mapped yet only get their properties recorded, `place_new_window` picks them up.
+    pub fn apply_window_rules(&mut self, window: &WindowElement) {
+        let properties = evaluate_rules(window);
+        let previous = window.applied_rules().0.replace(properties.clone());
+        if properties == previous {
+            return;
+        }
+        let mapped = self.space.element_location(window).is_some();
+
+        // scratchpad windows never take a cell
+        if let Some(floating) = properties.floating.filter(|_| !window.is_scratchpad()) {
+            window.set_floating(floating);
+        }
+        if let Some(ssd) = properties.ssd.filter(|_| properties.ssd != previous.ssd) {
+            self.set_window_ssd(window, ssd);
+        }
+        if mapped && properties.placement_differs(&previous) {
+            if properties.output != previous.output || properties.cell != previous.cell {
+                // leave the current cell so the rule's board and cell are used
+                window.set_board_cell(None);
+            }
+            let output = window.board_output(&self.space);
+            place_new_window(&mut self.space, output.as_ref(), window, false);
+        }
+        if let Some(class) = properties
+            .stacking
+            .filter(|_| properties.stacking != previous.stacking)
+        {
+            if mapped {
+                self.set_stacking(window, class);
+            } else {
+                window.set_stacking_class(class);
+            }
+        }
+        if mapped && properties.fullscreen != previous.fullscreen {
+            self.apply_fullscreen_rule(window);
+        }
+        if properties.inhibit_idle != previous.inhibit_idle {
+            self.update_idle_inhibit();
+        }
+    }
+
+    /// Fullscreens or unfullscreens the window as its rules ask for
+    pub fn apply_fullscreen_rule(&mut self, window: &WindowElement) {
+        match window.rule_properties().fullscreen {
+            Some(true) => self.fullscreen_window(window, None),
+            Some(false) => self.unfullscreen_window(window),
+            None => {}
+        }
+    }
+
+    fn set_window_ssd(&mut self, window: &WindowElement, ssd: bool) {
+        match window.0.underlying_surface() {
+            WindowSurface::Wayland(toplevel) => {
+                // the decorations change once the client acked the new mode
+                toplevel.with_pending_state(|state| {
+                    state.decoration_mode = Some(if ssd { Mode::ServerSide } else { Mode::ClientSide });
+                });
+                if toplevel.is_initial_configure_sent() {
+                    toplevel.send_pending_configure();
+                }
+            }
+            #[cfg(feature = "xwayland")]
+            WindowSurface::X11(_) => window.set_ssd(ssd),
+        }
+    }
+
+    /// Keeps the session from going idle while a window with the `inhibit_idle` rule is mapped
+    pub fn update_idle_inhibit(&mut self) {
+        let inhibited = self.space.elements().any(WindowElement::inhibits_idle);
+        self.idle_notifier_state.set_is_inhibited(inhibited);
+    }
+}
@@ -0,0 +1,158 @@
+use std::{cell::RefCell, process::Command};
+
+use smithay::utils::IsAlive;
+use tracing::{error, info, warn};
+
+use crate::{
+    config::ScratchpadConfig,
+    state::{AnvilState, Backend},
+};
+
+use super::{output_at, place_floating, set_hidden, window_identity, WindowElement};
+
+/// Name of the scratchpad a window was adopted by
+#[derive(Debug, Default)]
+struct Scratchpad(RefCell<Option<String>>);
+
+impl WindowElement {
+    /// Name of the scratchpad showing and hiding the window
+    pub fn scratchpad(&self) -> Option<String> {
+        self.user_data()
+            .get::<Scratchpad>()
+            .and_then(|scratchpad| scratchpad.0.borrow().clone())
+    }
+
+    /// Whether the window belongs to a scratchpad, it never takes a cell then
+    pub fn is_scratchpad(&self) -> bool {
+        self.scratchpad().is_some()
+    }
+
+    fn set_scratchpad(&self, name: &str) {
+        self.user_data().insert_if_missing(Scratchpad::default);
+        *self.user_data().get::<Scratchpad>().unwrap().0.borrow_mut() = Some(name.to_string());
+    }
+}
+
+fn matches_scratchpad(window: &WindowElement, config: &ScratchpadConfig) -> bool {
+    let (app_id, _) = window_identity(window);
+    !app_id.is_empty() && config.app_id.is_match(&app_id)
+}
+
+impl<BackendData: Backend> AnvilState<BackendData> {
+    /// Shows the window of the scratchpad floating on the focused output, or hides it if it is shown.
+    ///
+    /// Without a matching window the scratchpad's command is started,
+    /// the first matching window mapping afterwards is adopted.
+    pub fn toggle_scratchpad(&mut self, name: &str) {
+        let Some(config) = crate::config::with_config(|config| config.scratchpads.get(name).cloned()) else {
+            warn!(name, "Unknown scratchpad");
+            return;
+        };
+        let window = self
+            .scratchpads
+            .get(name)
+            .filter(|window| window.alive())
+            .cloned()
+            .or_else(|| {
+                // an instance started before, e.g. from a terminal
+                self.space
+                    .elements()
+                    .chain(self.minimized.iter())
+                    .find(|window| !window.is_scratchpad() && matches_scratchpad(window, &config))
+                    .cloned()
+            });
+        let Some(window) = window else {
+            self.spawn_scratchpad(name, &config);
+            return;
+        };
+
+        if window.is_scratchpad() && self.space.element_location(&window).is_some() {
+            self.hide_scratchpad(&window);
+        } else {
+            // a tiled window gives up its cell like a closed window
+            let cell = window.board_output(&self.space).zip(window.board_cell());
+            self.adopt_scratchpad(name, &window);
+            self.show_scratchpad(&window);
+            if let Some((output, index)) = cell {
+                self.cell_released(&output, index);
+            }
+        }
+    }
+
+    /// Adopts a newly mapped window for the scratchpad that started it.
+    ///
+    /// Returns whether the window belongs to a scratchpad now, it still has to be shown.
+    pub fn adopt_pending_scratchpad(&mut self, window: &WindowElement) -> bool {
+        if self.pending_scratchpads.is_empty() || window.is_scratchpad() {
+            return false;
+        }
+        let position = crate::config::with_config(|config| {
+            self.pending_scratchpads.iter().position(|name| {
+                config
+                    .scratchpads
+                    .get(name)
+                    .is_some_and(|config| matches_scratchpad(window, config))
+            })
+        });
+        let Some(position) = position else {
+            return false;
+        };
+        let name = self.pending_scratchpads.remove(position);
+        self.adopt_scratchpad(&name, window);
+        true
+    }
+
+    fn adopt_scratchpad(&mut self, name: &str, window: &WindowElement) {
+        window.set_scratchpad(name);
+        window.set_floating(true);
+        self.pending_scratchpads.retain(|pending| pending != name);
+        self.scratchpads.insert(name.to_string(), window.clone());
+    }
+
+    /// Maps the scratchpad window centered on the output of the focused window,
+    /// or the output under the pointer
+    pub fn show_scratchpad(&mut self, window: &WindowElement) {
+        let output = self
+            .focused_window()
+            .filter(|focused| focused != window)
+            .and_then(|focused| focused.board_output(&self.space))
+            .or_else(|| output_at(&self.space, self.pointer.current_location()));
+
+        self.forget_minimized(window);
+        set_hidden(window, false);
+        place_floating(&mut self.space, output.as_ref(), window, true);
+        self.focus_window(window);
+    }
+
+    fn hide_scratchpad(&mut self, window: &WindowElement) {
+        let had_focus = self.focused_window().as_ref() == Some(window);
+        self.space.unmap_elem(window);
+        self.focus_stack.retain(|w| w != window);
+        set_hidden(window, true);
+        self.update_idle_inhibit();
+        if had_focus {
+            self.focus_previous();
+        }
+    }
+
+    fn spawn_scratchpad(&mut self, name: &str, config: &ScratchpadConfig) {
+        let Some(command) = config.command.as_deref() else {
+            warn!(name, "No window for scratchpad and no command to start one");
+            return;
+        };
+        info!(name, command, "Starting scratchpad");
+        match Command::new("sh")
+            .arg("-c")
+            .arg(command)
+            .envs(self.client_env())
+            .spawn()
+        {
+            Ok(_) => {
+                if !self.pending_scratchpads.iter().any(|pending| pending == name) {
+                    self.pending_scratchpads.push(name.to_string());
+                }
+            }
+            Err(err) => error!(command, %err, "Failed to start scratchpad"),
+        }
+    }
+}
@@ -14,7 +14,7 @@ use smithay::{
 
 use std::cell::{RefCell, RefMut};
 
-use crate::{state::Backend, AnvilState};
+use crate::{config::StackingClass, state::Backend, AnvilState};
 
 use super::WindowElement;
 
@@ -29,9 +29,15 @@ pub struct HeaderBar {
     pub width: u32,
     pub close_button_hover: bool,
     pub maximize_button_hover: bool,
+    pub minimize_button_hover: bool,
     pub background: SolidColorBuffer,
     pub close_button: SolidColorBuffer,
     pub maximize_button: SolidColorBuffer,
+    pub minimize_button: SolidColorBuffer,
+    pub stacking: StackingClass,
+    pub stacking_indicator: SolidColorBuffer,
+    /// The window wants attention and the blinking highlight is lit
+    pub urgent: bool,
 }
 
 const BG_COLOR: [f32; 4] = [0.75f32, 0.9f32, 0.78f32, 1f32];
@@ -39,10 +45,17 @@ const MAX_COLOR: [f32; 4] = [1f32, 0.965f32, 0.71f32, 1f32];
 const CLOSE_COLOR: [f32; 4] = [1f32, 0.66f32, 0.612f32, 1f32];
 const MAX_COLOR_HOVER: [f32; 4] = [0.71f32, 0.624f32, 0f32, 1f32];
 const CLOSE_COLOR_HOVER: [f32; 4] = [0.75f32, 0.11f32, 0.016f32, 1f32];
+const MIN_COLOR: [f32; 4] = [0.71f32, 0.82f32, 1f32, 1f32];
+const MIN_COLOR_HOVER: [f32; 4] = [0.1f32, 0.35f32, 0.75f32, 1f32];
+const ABOVE_COLOR: [f32; 4] = [0.2f32, 0.45f32, 0.85f32, 1f32];
+const BELOW_COLOR: [f32; 4] = [0.45f32, 0.45f32, 0.45f32, 1f32];
+const URGENT_COLOR: [f32; 4] = [1f32, 0.6f32, 0.2f32, 1f32];
 
 pub const HEADER_BAR_HEIGHT: i32 = 32;
 const BUTTON_HEIGHT: u32 = HEADER_BAR_HEIGHT as u32;
 const BUTTON_WIDTH: u32 = 32;
+const INDICATOR_SIZE: i32 = 12;
+const INDICATOR_OFFSET: i32 = (HEADER_BAR_HEIGHT - INDICATOR_SIZE) / 2;
 
 impl HeaderBar {
     pub fn pointer_enter(&mut self, loc: Point<f64, Logical>) {
@@ -82,6 +95,12 @@ impl HeaderBar {
                     }
                 };
             }
+            Some(loc) if loc.x >= (self.width - (BUTTON_WIDTH * 3)) as f64 => {
+                let window = window.clone();
+                state
+                    .handle
+                    .insert_idle(move |data| data.minimize_window(&window));
+            }
             Some(_) => {
                 match window.0.underlying_surface() {
                     WindowSurface::Wayland(w) => {
@@ -114,6 +133,7 @@ impl HeaderBar {
         match self.pointer_loc.as_ref() {
             Some(loc) if loc.x >= (self.width - BUTTON_WIDTH) as f64 => {}
             Some(loc) if loc.x >= (self.width - (BUTTON_WIDTH * 2)) as f64 => {}
+            Some(loc) if loc.x >= (self.width - (BUTTON_WIDTH * 3)) as f64 => {}
             Some(_) => {
                 match window.0.underlying_surface() {
                     WindowSurface::Wayland(w) => {
@@ -165,6 +185,12 @@ impl HeaderBar {
                     }
                 };
             }
+            Some(loc) if loc.x >= (self.width - (BUTTON_WIDTH * 3)) as f64 => {
+                let window = window.clone();
+                state
+                    .handle
+                    .insert_idle(move |data| data.minimize_window(&window));
+            }
             _ => {}
         };
     }
@@ -175,8 +201,18 @@ impl HeaderBar {
             return;
         }
 
+        let background = if self.urgent { URGENT_COLOR } else { BG_COLOR };
         self.background
-            .update((width as i32, HEADER_BAR_HEIGHT), BG_COLOR);
+            .update((width as i32, HEADER_BAR_HEIGHT), background);
+        match self.stacking {
+            StackingClass::Above => self
+                .stacking_indicator
+                .update((INDICATOR_SIZE, INDICATOR_SIZE), ABOVE_COLOR),
+            StackingClass::Below => self
+                .stacking_indicator
+                .update((INDICATOR_SIZE, INDICATOR_SIZE), BELOW_COLOR),
+            StackingClass::Normal => {}
+        }
 
         let mut needs_redraw_buttons = false;
         if width != self.width {
@@ -227,6 +263,78 @@ impl HeaderBar {
                 .update((BUTTON_WIDTH as i32, BUTTON_HEIGHT as i32), MAX_COLOR);
             self.maximize_button_hover = false;
         }
+
+        if self
+            .pointer_loc
+            .as_ref()
+            .map(|l| l.x >= (width - BUTTON_WIDTH * 3) as f64 && l.x <= (width - BUTTON_WIDTH * 2) as f64)
+            .unwrap_or(false)
+            && (needs_redraw_buttons || !self.minimize_button_hover)
+        {
+            self.minimize_button
+                .update((BUTTON_WIDTH as i32, BUTTON_HEIGHT as i32), MIN_COLOR_HOVER);
+            self.minimize_button_hover = true;
+        } else if !self
+            .pointer_loc
+            .as_ref()
+            .map(|l| l.x >= (width - BUTTON_WIDTH * 3) as f64 && l.x <= (width - BUTTON_WIDTH * 2) as f64)
+            .unwrap_or(false)
+            && (needs_redraw_buttons || self.minimize_button_hover)
+        {
+            self.minimize_button
+                .update((BUTTON_WIDTH as i32, BUTTON_HEIGHT as i32), MIN_COLOR);
+            self.minimize_button_hover = false;
+        }
+    }
+}
+
+impl HeaderBar {
+    /// Buffers making up the header bar with their offsets and colors, front to back
+    pub fn parts(&self) -> Vec<(&SolidColorBuffer, Point<i32, Logical>, [f32; 4])> {
+        let header_end_offset: Point<i32, Logical> = Point::from((self.width as i32, 0));
+        let button_offset: Point<i32, Logical> = Point::from((BUTTON_WIDTH as i32, 0));
+        let hover_color = |hover: bool, color: [f32; 4], hover_color: [f32; 4]| {
+            if hover {
+                hover_color
+            } else {
+                color
+            }
+        };
+
+        let mut parts = vec![
+            (
+                &self.close_button,
+                header_end_offset - button_offset,
+                hover_color(self.close_button_hover, CLOSE_COLOR, CLOSE_COLOR_HOVER),
+            ),
+            (
+                &self.maximize_button,
+                header_end_offset - button_offset.upscale(2),
+                hover_color(self.maximize_button_hover, MAX_COLOR, MAX_COLOR_HOVER),
+            ),
+            (
+                &self.minimize_button,
+                header_end_offset - button_offset.upscale(3),
+                hover_color(self.minimize_button_hover, MIN_COLOR, MIN_COLOR_HOVER),
+            ),
+        ];
+        // marks windows kept above or below the others
+        match self.stacking {
+            StackingClass::Above => parts.push((
+                &self.stacking_indicator,
+                Point::from((INDICATOR_OFFSET, INDICATOR_OFFSET)),
+                ABOVE_COLOR,
+            )),
+            StackingClass::Below => parts.push((
+                &self.stacking_indicator,
+                Point::from((INDICATOR_OFFSET, INDICATOR_OFFSET)),
+                BELOW_COLOR,
+            )),
+            StackingClass::Normal => {}
+        }
+        let background = if self.urgent { URGENT_COLOR } else { BG_COLOR };
+        parts.push((&self.background, Point::default(), background));
+        parts
     }
 }
 
@@ -240,29 +348,19 @@ impl<R: Renderer> AsRenderElements<R> for HeaderBar {
         scale: smithay::utils::Scale<f64>,
         alpha: f32,
     ) -> Vec<C> {
-        let header_end_offset: Point<i32, Logical> = Point::from((self.width as i32, 0));
-        let button_offset: Point<i32, Logical> = Point::from((BUTTON_WIDTH as i32, 0));
-
-        vec![
-            SolidColorRenderElement::from_buffer(
-                &self.close_button,
-                location + (header_end_offset - button_offset).to_physical_precise_round(scale),
-                scale,
-                alpha,
-                Kind::Unspecified,
-            )
-            .into(),
-            SolidColorRenderElement::from_buffer(
-                &self.maximize_button,
-                location + (header_end_offset - button_offset.upscale(2)).to_physical_precise_round(scale),
-                scale,
-                alpha,
-                Kind::Unspecified,
-            )
-            .into(),
-            SolidColorRenderElement::from_buffer(&self.background, location, scale, alpha, Kind::Unspecified)
-                .into(),
-        ]
+        self.parts()
+            .into_iter()
+            .map(|(buffer, offset, _)| {
+                SolidColorRenderElement::from_buffer(
+                    buffer,
+                    location + offset.to_physical_precise_round(scale),
+                    scale,
+                    alpha,
+                    Kind::Unspecified,
+                )
+                .into()
+            })
+            .collect()
     }
 }
 
@@ -276,9 +374,14 @@ impl WindowElement {
                     width: 0,
                     close_button_hover: false,
                     maximize_button_hover: false,
+                    minimize_button_hover: false,
                     background: SolidColorBuffer::default(),
                     close_button: SolidColorBuffer::default(),
                     maximize_button: SolidColorBuffer::default(),
+                    minimize_button: SolidColorBuffer::default(),
+                    stacking: StackingClass::Normal,
+                    stacking_indicator: SolidColorBuffer::default(),
+                    urgent: false,
                 },
             })
         });
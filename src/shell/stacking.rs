@@ -0,0 +1,67 @@
+use std::cell::Cell;
+
+use smithay::desktop::space::SpaceElement;
+
+use crate::{
+    config::StackingClass,
+    state::{AnvilState, Backend},
+};
+
+use super::{transients_of, WindowElement};
+
+#[derive(Debug, Default)]
+struct Stacking(Cell<StackingClass>);
+
+impl WindowElement {
+    pub fn stacking_class(&self) -> StackingClass {
+        self.user_data()
+            .get::<Stacking>()
+            .map(|stacking| stacking.0.get())
+            .unwrap_or_default()
+    }
+
+    pub fn set_stacking_class(&self, class: StackingClass) {
+        self.user_data().insert_if_missing(Stacking::default);
+        self.user_data().get::<Stacking>().unwrap().0.set(class);
+    }
+
+    /// Stacking index of the window.
+    ///
+    /// Windows of a higher class are always above windows of a lower class,
+    /// floating windows are above tiled ones of the same class.
+    pub fn stacking_z_index(&self) -> u8 {
+        let base = SpaceElement::z_index(&self.0);
+        let base = match self.stacking_class() {
+            StackingClass::Above => base + 2,
+            StackingClass::Normal => base,
+            StackingClass::Below => base - 2,
+        };
+        base + self.is_floating() as u8
+    }
+}
+
+impl<BackendData: Backend> AnvilState<BackendData> {
+    /// Moves the window into another stacking class, its dialogs follow it
+    pub fn set_stacking(&mut self, window: &WindowElement, class: StackingClass) {
+        let transients = transients_of(&self.space, window);
+        for window in std::iter::once(window).chain(transients.iter()) {
+            window.set_stacking_class(class);
+            // the space only sorts by stacking index when an element is (re)inserted
+            self.space.raise_element(window, false);
+        }
+    }
+
+    /// Puts the focused window into the given stacking class, or back into the normal one
+    /// if it already is in that class
+    pub fn toggle_stacking(&mut self, class: StackingClass) {
+        let Some(window) = self.focused_window() else {
+            return;
+        };
+        let class = if window.stacking_class() == class {
+            StackingClass::Normal
+        } else {
+            class
+        };
+        self.set_stacking(&window, class);
+    }
+}
@@ -0,0 +1,198 @@
+use std::cell::RefCell;
+
+use smithay::{
+    backend::renderer::{element::solid::SolidColorBuffer, Color32F},
+    input::keyboard::ModifiersState,
+    output::Output,
+    utils::{Logical, Point, Rectangle, Size},
+};
+
+use crate::state::{AnvilState, Backend};
+
+use super::{output_at, WindowElement};
+
+const SLOT_COLOR: Color32F = Color32F::new(0.2, 0.2, 0.24, 0.9);
+const SELECTED_COLOR: Color32F = Color32F::new(0.45, 0.55, 0.85, 1.0);
+
+/// Thumbnails per row
+const SWITCHER_COLUMNS: usize = 6;
+/// Share of the output the thumbnails may cover
+const SWITCHER_FILL: f64 = 0.8;
+const THUMBNAIL_MAX_WIDTH: i32 = 320;
+/// Gap around each thumbnail, the background of the slot shows in it
+const THUMBNAIL_PADDING: i32 = 12;
+
+/// Alt-Tab style switcher, thumbnails of the windows in the order they last had focus
+#[derive(Debug)]
+pub struct Switcher {
+    pub windows: Vec<WindowElement>,
+    pub selected: usize,
+    /// Output the switcher is shown on
+    pub output: Output,
+    /// Modifiers held when the switcher was opened, releasing them focuses the selected window
+    modifiers: ModifiersState,
+    backgrounds: RefCell<Vec<SolidColorBuffer>>,
+}
+
+impl Switcher {
+    /// Area of the thumbnail of the window at `index`, relative to the output.
+    ///
+    /// The thumbnails are laid out in rows centered on the output.
+    pub fn thumbnail_rect(&self, output_size: Size<i32, Logical>, index: usize) -> Rectangle<i32, Logical> {
+        let count = self.windows.len().max(1);
+        let columns = count.min(SWITCHER_COLUMNS);
+        let rows = count.div_ceil(columns);
+
+        let slot_width = ((output_size.w as f64 * SWITCHER_FILL) as i32 / columns as i32)
+            .min(THUMBNAIL_MAX_WIDTH + THUMBNAIL_PADDING * 2);
+        let slot_height =
+            (slot_width * 2 / 3).min((output_size.h as f64 * SWITCHER_FILL) as i32 / rows as i32);
+        let origin = Point::<i32, Logical>::from((
+            (output_size.w - slot_width * columns as i32) / 2,
+            (output_size.h - slot_height * rows as i32) / 2,
+        ));
+
+        let (column, row) = (index % columns, index / columns);
+        Rectangle::new(
+            origin
+                + Point::from((
+                    slot_width * column as i32 + THUMBNAIL_PADDING,
+                    slot_height * row as i32 + THUMBNAIL_PADDING,
+                )),
+            Size::from((
+                slot_width - THUMBNAIL_PADDING * 2,
+                slot_height - THUMBNAIL_PADDING * 2,
+            )),
+        )
+    }
+
+    /// Background behind the thumbnail at `index`, highlighted for the selected window.
+    ///
+    /// Returns the area it covers along with the buffer.
+    pub fn slot_background(
+        &self,
+        output_size: Size<i32, Logical>,
+        index: usize,
+    ) -> (Rectangle<i32, Logical>, SolidColorBuffer) {
+        let mut rect = self.thumbnail_rect(output_size, index);
+        rect.loc -= Point::from((THUMBNAIL_PADDING / 2, THUMBNAIL_PADDING / 2));
+        rect.size += Size::from((THUMBNAIL_PADDING, THUMBNAIL_PADDING));
+        let color = if index == self.selected {
+            SELECTED_COLOR
+        } else {
+            SLOT_COLOR
+        };
+
+        let mut backgrounds = self.backgrounds.borrow_mut();
+        if backgrounds.len() <= index {
+            backgrounds.resize_with(index + 1, Default::default);
+        }
+        backgrounds[index].update(rect.size, color);
+        (rect, backgrounds[index].clone())
+    }
+
+    /// Whether none of the modifiers the switcher was opened with is held anymore.
+    ///
+    /// A switcher opened without Alt, Logo or Ctrl is never released, it stays open
+    /// until a window is picked with Return or it is cancelled with Escape.
+    pub fn released(&self, modifiers: &ModifiersState) -> bool {
+        let opened_with = &self.modifiers;
+        (opened_with.alt || opened_with.logo || opened_with.ctrl)
+            && !(opened_with.alt && modifiers.alt
+                || opened_with.logo && modifiers.logo
+                || opened_with.ctrl && modifiers.ctrl)
+    }
+}
+
+impl<BackendData: Backend> AnvilState<BackendData> {
+    /// Opens the switcher on the window focused before the current one,
+    /// or moves its selection on to the next (or previous) window
+    pub fn switch_window(&mut self, backwards: bool) {
+        if let Some(switcher) = self.switcher.as_mut() {
+            let count = switcher.windows.len();
+            switcher.selected = if backwards {
+                (switcher.selected + count - 1) % count
+            } else {
+                (switcher.selected + 1) % count
+            };
+            return;
+        }
+
+        // most recently focused first, windows never focused yet at the end
+        let mut windows = self
+            .focus_stack
+            .iter()
+            .filter(|window| self.space.element_location(window).is_some())
+            .cloned()
+            .collect::<Vec<_>>();
+        let unfocused = self
+            .space
+            .elements()
+            .filter(|window| !windows.contains(window))
+            .cloned()
+            .collect::<Vec<_>>();
+        windows.extend(unfocused);
+        windows.retain(WindowElement::accepts_focus);
+        if windows.is_empty() {
+            return;
+        }
+
+        let output = self
+            .focused_window()
+            .and_then(|window| window.board_output(&self.space))
+            .or_else(|| output_at(&self.space, self.pointer.current_location()))
+            .or_else(|| self.space.outputs().next().cloned());
+        let Some(output) = output else {
+            return;
+        };
+        let selected = match (backwards, windows.len()) {
+            (_, 1) => 0,
+            (true, count) => count - 1,
+            (false, _) => 1,
+        };
+        self.switcher = Some(Switcher {
+            windows,
+            selected,
+            output,
+            modifiers: self.seat.get_keyboard().unwrap().modifier_state(),
+            backgrounds: RefCell::default(),
+        });
+    }
+
+    /// Closes the switcher and focuses and raises the selected window,
+    /// scrolling it into view wherever it is
+    pub fn switcher_select(&mut self) {
+        let Some(switcher) = self.switcher.take() else {
+            return;
+        };
+        let Some(window) = switcher.windows.get(switcher.selected) else {
+            return;
+        };
+        if self.space.element_location(window).is_none() {
+            return;
+        }
+        self.focus_window(window);
+        self.reveal_window(window);
+    }
+
+    /// Closes the switcher, the focus stays where it was
+    pub fn switcher_cancel(&mut self) {
+        self.switcher = None;
+    }
+
+    /// Drops a window that went away from the switcher
+    pub fn forget_switcher_window(&mut self, window: &WindowElement) {
+        let Some(switcher) = self.switcher.as_mut() else {
+            return;
+        };
+        let Some(index) = switcher.windows.iter().position(|w| w == window) else {
+            return;
+        };
+        switcher.windows.remove(index);
+        if switcher.windows.is_empty() {
+            self.switcher = None;
+        } else if switcher.selected > index || switcher.selected == switcher.windows.len() {
+            switcher.selected -= 1;
+        }
+    }
+}
@@ -0,0 +1,98 @@
+use smithay::{
+    desktop::{Space, WindowSurface},
+    utils::{Logical, Point},
+    wayland::seat::WaylandFocus,
+};
+
+use super::WindowElement;
+
+impl WindowElement {
+    /// Whether the window is a dialog or child of another toplevel
+    pub fn is_transient(&self) -> bool {
+        match self.0.underlying_surface() {
+            WindowSurface::Wayland(toplevel) => toplevel.parent().is_some(),
+            #[cfg(feature = "xwayland")]
+            WindowSurface::X11(surface) => {
+                surface.is_transient_for().is_some()
+                    || surface.window_type() == Some(smithay::xwayland::xwm::WmWindowType::Dialog)
+            }
+        }
+    }
+}
+
+/// Toplevel the window is a dialog or child of.
+///
+/// X11 dialogs without WM_TRANSIENT_FOR belong to the topmost other window of their WM_CLASS.
+pub fn parent_window(space: &Space<WindowElement>, window: &WindowElement) -> Option<WindowElement> {
+    match window.0.underlying_surface() {
+        WindowSurface::Wayland(toplevel) => {
+            let parent = toplevel.parent()?;
+            space
+                .elements()
+                .find(|w| w.wl_surface().as_deref() == Some(&parent))
+                .cloned()
+        }
+        #[cfg(feature = "xwayland")]
+        WindowSurface::X11(surface) => {
+            if let Some(parent) = surface.is_transient_for() {
+                return space
+                    .elements()
+                    .find(|w| w.0.x11_surface().is_some_and(|s| s.window_id() == parent))
+                    .cloned();
+            }
+            if surface.window_type() != Some(smithay::xwayland::xwm::WmWindowType::Dialog) {
+                return None;
+            }
+            let class = surface.class();
+            space
+                .elements()
+                .rev()
+                .find(|w| {
+                    *w != window && !w.is_transient() && w.0.x11_surface().is_some_and(|s| s.class() == class)
+                })
+                .cloned()
+        }
+    }
+}
+
+/// Floating windows belonging to the given parent
+pub fn transients_of(space: &Space<WindowElement>, parent: &WindowElement) -> Vec<WindowElement> {
+    space
+        .elements()
+        .filter(|window| *window != parent && window.is_floating())
+        .filter(|window| parent_window(space, window).as_ref() == Some(parent))
+        .cloned()
+        .collect()
+}
+
+/// Maps the window at the given location, its floating transient windows move along
+pub fn map_with_transients(
+    space: &mut Space<WindowElement>,
+    window: &WindowElement,
+    location: Point<i32, Logical>,
+    activate: bool,
+) {
+    let previous = space.element_location(window);
+    let transients = transients_of(space, window);
+    space.map_element(window.clone(), location, activate);
+
+    let Some(delta) = previous.map(|previous| location - previous) else {
+        return;
+    };
+    if delta == Point::default() {
+        return;
+    }
+    for transient in transients {
+        let Some(transient_location) = space.element_location(&transient) else {
+            continue;
+        };
+        map_with_transients(space, &transient, transient_location + delta, false);
+
+        #[cfg(feature = "xwayland")]
+        if let Some(surface) = transient.0.x11_surface() {
+            if let Some(bbox) = space.element_bbox(&transient) {
+                let _ = surface.configure(bbox);
+            }
+        }
+    }
+}
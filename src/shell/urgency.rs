@@ -0,0 +1,120 @@
+use std::{
+    cell::Cell,
+    time::{Duration, Instant},
+};
+
+use smithay::reexports::calloop::timer::{TimeoutAction, Timer};
+
+use crate::state::{AnvilState, Backend};
+
+use super::WindowElement;
+
+/// Time the urgency highlight stays on or off while blinking
+const BLINK_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Attention state of a window
+#[derive(Debug, Default)]
+struct Urgency {
+    /// Time the window started to want attention
+    since: Cell<Option<Instant>>,
+    /// Whether the blinking highlight is currently lit
+    lit: Cell<bool>,
+}
+
+impl WindowElement {
+    fn urgency(&self) -> &Urgency {
+        self.user_data().insert_if_missing(Urgency::default);
+        self.user_data().get::<Urgency>().unwrap()
+    }
+
+    /// Time the window became urgent, none while it doesn't want attention
+    pub fn urgent_since(&self) -> Option<Instant> {
+        self.user_data()
+            .get::<Urgency>()
+            .and_then(|urgency| urgency.since.get())
+    }
+
+    pub fn is_urgent(&self) -> bool {
+        self.urgent_since().is_some()
+    }
+
+    /// Whether the urgency highlight is lit, it blinks while the window is urgent
+    pub fn urgency_blink(&self) -> bool {
+        self.is_urgent() && self.urgency().lit.get()
+    }
+}
+
+impl<BackendData: Backend> AnvilState<BackendData> {
+    /// Marks the window as wanting attention, or clears the mark.
+    ///
+    /// The focused window never becomes urgent, focusing a window clears its mark.
+    pub fn set_urgent(&mut self, window: &WindowElement, urgent: bool) {
+        if urgent == window.is_urgent() || urgent && self.focused_window().as_ref() == Some(window) {
+            return;
+        }
+        window.urgency().since.set(urgent.then(Instant::now));
+        window.urgency().lit.set(urgent);
+        if urgent && self.blink_timer.is_none() {
+            self.blink_timer = Some(
+                self.handle
+                    .insert_source(Timer::from_duration(BLINK_INTERVAL), |_, _, state| {
+                        if state.step_urgency_blink() {
+                            TimeoutAction::ToDuration(BLINK_INTERVAL)
+                        } else {
+                            state.blink_timer = None;
+                            TimeoutAction::Drop
+                        }
+                    })
+                    .expect("failed to schedule urgency blink"),
+            );
+        }
+    }
+
+    /// Toggles the highlight of the urgent windows and redraws the outputs showing them.
+    ///
+    /// Returns whether any window is still urgent.
+    fn step_urgency_blink(&mut self) -> bool {
+        let urgent = self.urgent_windows();
+        let mut outputs = Vec::new();
+        for window in &urgent {
+            let lit = &window.urgency().lit;
+            lit.set(!lit.get());
+            for output in self.space.outputs_for_element(window) {
+                if !outputs.contains(&output) {
+                    outputs.push(output);
+                }
+            }
+        }
+        for output in &outputs {
+            self.backend_data.reset_buffers(output);
+        }
+        !urgent.is_empty()
+    }
+
+    /// Urgent windows, minimized ones included, the one waiting longest first
+    pub fn urgent_windows(&self) -> Vec<WindowElement> {
+        let mut windows = self
+            .space
+            .elements()
+            .chain(self.minimized.iter())
+            .filter(|window| window.is_urgent())
+            .cloned()
+            .collect::<Vec<_>>();
+        windows.sort_by_key(|window| window.urgent_since());
+        windows
+    }
+
+    /// Focuses and raises the window that has been urgent the longest,
+    /// restoring it if it is minimized and scrolling it into view
+    pub fn focus_urgent(&mut self) {
+        let Some(window) = self.urgent_windows().into_iter().next() else {
+            return;
+        };
+        if self.minimized.contains(&window) {
+            self.restore_window(&window);
+            return;
+        }
+        self.focus_window(&window);
+        self.reveal_window(&window);
+    }
+}
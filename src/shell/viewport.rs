@@ -0,0 +1,269 @@
+use std::{cell::Cell, time::Duration};
+
+use smithay::{
+    desktop::Space,
+    input::pointer::MotionEvent,
+    output::Output,
+    reexports::calloop::timer::{TimeoutAction, Timer},
+    utils::{Logical, Point, Rectangle, SERIAL_COUNTER},
+};
+
+use crate::{
+    config::FollowFocus,
+    state::{AnvilState, Backend},
+};
+
+use super::WindowElement;
+
+/// Part of the board an output is showing.
+///
+/// Outputs keep their place in the side-by-side output layout (the home position)
+/// and are mapped into the space shifted by the distance they were panned.
+#[derive(Debug, Default)]
+pub struct Viewport {
+    home: Cell<Option<Point<i32, Logical>>>,
+    pan: Cell<Point<i32, Logical>>,
+    /// Location in the space the output is scrolling towards
+    scroll_target: Cell<Option<Point<i32, Logical>>>,
+}
+
+/// Interval between two steps of a viewport scroll animation
+const SCROLL_INTERVAL: Duration = Duration::from_millis(16);
+/// Fraction of the remaining distance covered by each step
+const SCROLL_FACTOR: f64 = 0.25;
+
+fn viewport(output: &Output) -> &Viewport {
+    output.user_data().insert_if_missing(Viewport::default);
+    output.user_data().get::<Viewport>().unwrap()
+}
+
+/// Location of the output in the unpanned output layout
+pub fn output_home(space: &Space<WindowElement>, output: &Output) -> Option<Point<i32, Logical>> {
+    let viewport = viewport(output);
+    viewport
+        .home
+        .get()
+        .or_else(|| Some(space.output_geometry(output)?.loc - viewport.pan.get()))
+}
+
+/// Maps the output at its home location, keeping the current pan offset
+pub fn map_output_home(space: &mut Space<WindowElement>, output: &Output, home: Point<i32, Logical>) {
+    let viewport = viewport(output);
+    viewport.home.set(Some(home));
+    space.map_output(output, home + viewport.pan.get());
+}
+
+/// Moves the viewport of the output across the board
+pub fn pan_output(space: &mut Space<WindowElement>, output: &Output, delta: Point<i32, Logical>) {
+    let Some(home) = output_home(space, output) else {
+        return;
+    };
+    let viewport = viewport(output);
+    viewport.pan.set(viewport.pan.get() + delta);
+    space.map_output(output, home + viewport.pan.get());
+}
+
+/// Translates a location in the unpanned output layout into the space
+pub fn layout_to_space(space: &Space<WindowElement>, location: Point<f64, Logical>) -> Point<f64, Logical> {
+    space
+        .outputs()
+        .find_map(|output| {
+            let home = output_home(space, output)?;
+            let size = space.output_geometry(output)?.size;
+            Rectangle::new(home, size)
+                .contains(location.to_i32_round())
+                .then(|| location + viewport(output).pan.get().to_f64())
+        })
+        .unwrap_or(location)
+}
+
+impl<BackendData: Backend> AnvilState<BackendData> {
+    /// Pans the output and moves the pointer along, so it stays at the same spot on the screen
+    pub fn pan_viewport(&mut self, output: &Output, delta: Point<i32, Logical>) {
+        let pointer_location = self.pointer.current_location();
+        let pointer_on_output = self
+            .space
+            .output_geometry(output)
+            .map(|geo| geo.to_f64().contains(pointer_location))
+            .unwrap_or(false);
+
+        pan_output(&mut self.space, output, delta);
+
+        if pointer_on_output {
+            let location = pointer_location + delta.to_f64();
+            let pointer = self.pointer.clone();
+            let under = self.surface_under(location);
+            pointer.motion(
+                self,
+                under,
+                &MotionEvent {
+                    location,
+                    serial: SERIAL_COUNTER.next_serial(),
+                    time: self.clock.now().as_millis(),
+                },
+            );
+            pointer.frame(self);
+        }
+    }
+}
+
+/// Stops an ongoing scroll animation, e.g. because the user started panning
+pub fn cancel_scroll(output: &Output) {
+    viewport(output).scroll_target.set(None);
+}
+
+/// Distance the output still moves until its scroll animation ends
+pub fn pending_scroll(space: &Space<WindowElement>, output: &Output) -> Point<i32, Logical> {
+    match (
+        viewport(output).scroll_target.get(),
+        space.output_geometry(output),
+    ) {
+        (Some(target), Some(geometry)) => target - geometry.loc,
+        _ => Point::default(),
+    }
+}
+
+/// Pan needed to bring `window` into the visible area `output`
+fn follow_delta(
+    mode: FollowFocus,
+    output: Rectangle<i32, Logical>,
+    window: Rectangle<i32, Logical>,
+) -> Point<i32, Logical> {
+    let center = |start: i32, size: i32| start + size / 2;
+    let edge = |out_start: i32, out_size: i32, win_start: i32, win_size: i32| {
+        if win_size > out_size {
+            // can't fit anyway, show as much of it as possible
+            center(win_start, win_size) - center(out_start, out_size)
+        } else if win_start < out_start {
+            win_start - out_start
+        } else if win_start + win_size > out_start + out_size {
+            win_start + win_size - (out_start + out_size)
+        } else {
+            0
+        }
+    };
+
+    match mode {
+        FollowFocus::Off => Point::default(),
+        FollowFocus::Edge => Point::from((
+            edge(output.loc.x, output.size.w, window.loc.x, window.size.w),
+            edge(output.loc.y, output.size.h, window.loc.y, window.size.h),
+        )),
+        FollowFocus::Center => Point::from((
+            center(window.loc.x, window.size.w) - center(output.loc.x, output.size.w),
+            center(window.loc.y, window.size.h) - center(output.loc.y, output.size.h),
+        )),
+    }
+}
+
+impl<BackendData: Backend> AnvilState<BackendData> {
+    /// Smoothly scrolls the viewport closest to the window until the window is visible.
+    ///
+    /// Returns the distance the viewport is going to move.
+    pub fn scroll_to_window(&mut self, window: &WindowElement, mode: FollowFocus) -> Point<i32, Logical> {
+        if mode == FollowFocus::Off {
+            return Point::default();
+        }
+        let Some(geometry) = self.space.element_geometry(window) else {
+            return Point::default();
+        };
+
+        // windows on a board scroll into the output owning the board, others into the closest output
+        let board_output = window
+            .board_output(&self.space)
+            .and_then(|output| Some((output.clone(), self.space.output_geometry(&output)?)));
+        let window_center = geometry.loc.to_f64() + geometry.size.to_f64().downscale(2.0).to_point();
+        let Some((output, output_geometry)) = board_output.or_else(|| {
+            self.space
+                .outputs()
+                .filter_map(|o| Some((o.clone(), self.space.output_geometry(o)?)))
+                .min_by(|(_, a), (_, b)| {
+                    let distance = |geo: &Rectangle<i32, Logical>| {
+                        let clamped = Point::<f64, Logical>::from((
+                            window_center
+                                .x
+                                .clamp(geo.loc.x as f64, (geo.loc.x + geo.size.w) as f64),
+                            window_center
+                                .y
+                                .clamp(geo.loc.y as f64, (geo.loc.y + geo.size.h) as f64),
+                        ));
+                        (clamped.x - window_center.x).powi(2) + (clamped.y - window_center.y).powi(2)
+                    };
+                    distance(a).total_cmp(&distance(b))
+                })
+        }) else {
+            return Point::default();
+        };
+
+        let delta = follow_delta(mode, output_geometry, geometry);
+        if delta == Point::default() {
+            return delta;
+        }
+
+        viewport(&output)
+            .scroll_target
+            .set(Some(output_geometry.loc + delta));
+        // a cancelled scroll leaves the timer running until its next step, reuse it
+        if self.scroll_timer.is_none() {
+            self.scroll_timer = Some(
+                self.handle
+                    .insert_source(Timer::from_duration(SCROLL_INTERVAL), |_, _, state| {
+                        if state.step_scroll() {
+                            TimeoutAction::ToDuration(SCROLL_INTERVAL)
+                        } else {
+                            state.scroll_timer = None;
+                            TimeoutAction::Drop
+                        }
+                    })
+                    .expect("failed to schedule viewport scroll"),
+            );
+        }
+        delta
+    }
+
+    /// Scrolls a window the user explicitly asked for into view, even if the
+    /// viewport doesn't follow the focus otherwise
+    pub fn reveal_window(&mut self, window: &WindowElement) -> Point<i32, Logical> {
+        let mode = match crate::config::layout().follow_focus {
+            FollowFocus::Off => FollowFocus::Edge,
+            mode => mode,
+        };
+        self.scroll_to_window(window, mode)
+    }
+
+    /// Moves all scrolling viewports one step closer to their target,
+    /// returns whether any of them still has to move further
+    fn step_scroll(&mut self) -> bool {
+        let outputs = self.space.outputs().cloned().collect::<Vec<_>>();
+        let mut animating = false;
+        for output in outputs {
+            let viewport = viewport(&output);
+            let (Some(target), Some(geometry)) =
+                (viewport.scroll_target.get(), self.space.output_geometry(&output))
+            else {
+                continue;
+            };
+
+            let remaining = target - geometry.loc;
+            let step = remaining.to_f64().upscale(SCROLL_FACTOR).to_i32_round::<i32>();
+            let step = if step.x.abs() < 1 && step.y.abs() < 1 {
+                // close enough, snap into place
+                viewport.scroll_target.set(None);
+                remaining
+            } else {
+                animating = true;
+                step
+            };
+            self.pan_viewport(&output, step);
+        }
+        animating
+    }
+}
+
+/// Touchpad swipe panning an output
+#[derive(Debug)]
+pub struct PanGesture {
+    pub output: Output,
+    /// Part of the swipe not yet applied, viewports only move in whole pixels
+    pub remainder: Point<f64, Logical>,
+}
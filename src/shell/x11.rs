@@ -1,8 +1,12 @@
-use std::{cell::RefCell, os::unix::io::OwnedFd};
+use std::{
+    cell::RefCell,
+    os::unix::io::{AsFd, OwnedFd},
+};
 
 use smithay::{
     desktop::{space::SpaceElement, Window},
     input::pointer::Focus,
+    reexports::calloop::{generic::Generic, Interest, Mode, PostAction},
     utils::{Logical, Rectangle, SERIAL_COUNTER},
     wayland::{
         compositor::with_states,
@@ -20,16 +24,24 @@ use smithay::{
         xwayland_shell::{XWaylandShellHandler, XWaylandShellState},
     },
     xwayland::{
-        xwm::{Reorder, ResizeEdge as X11ResizeEdge, XwmId},
+        xwm::{Reorder, ResizeEdge as X11ResizeEdge, WmWindowProperty, XwmId},
         X11Surface, X11Wm, XwmHandler,
     },
 };
-use tracing::{error, trace};
+use tracing::{error, trace, warn};
+use x11rb::{
+    connection::Connection,
+    protocol::{
+        xproto::{ChangeWindowAttributesAux, ConnectionExt as _, EventMask},
+        Event,
+    },
+    rust_connection::RustConnection,
+};
 
 use crate::{focus::KeyboardFocusTarget, state::Backend, AnvilState};
 
 use super::{
-    place_new_window, FullscreenSurface, PointerMoveSurfaceGrab, PointerResizeSurfaceGrab, ResizeData,
+    map_with_transients, place_new_window, PointerMoveSurfaceGrab, PointerResizeSurfaceGrab, ResizeData,
     ResizeState, SurfaceData, TouchMoveSurfaceGrab, WindowElement,
 };
 
@@ -62,13 +74,50 @@ impl<BackendData: Backend> XwmHandler for AnvilState<BackendData> {
     fn map_window_request(&mut self, _xwm: XwmId, window: X11Surface) {
         window.set_mapped(true).unwrap();
         let window = WindowElement(Window::new_x11_window(window));
-        place_new_window(&mut self.space, self.pointer.current_location(), &window, true);
+        window.set_floating(window.wants_floating());
+        self.apply_window_rules(&window);
+        // a scratchpad window never takes a cell
+        if self.adopt_pending_scratchpad(&window) {
+            self.show_scratchpad(&window);
+        } else {
+            let output = self.new_window_output();
+            place_new_window(&mut self.space, output.as_ref(), &window, true);
+        }
         let bbox = self.space.element_bbox(&window).unwrap();
         let Some(xsurface) = window.0.x11_surface() else {
             unreachable!()
         };
         xsurface.configure(Some(bbox)).unwrap();
-        window.set_ssd(!xsurface.is_decorated());
+        let rules = window.rule_properties();
+        window.set_ssd(rules.ssd.unwrap_or(!xsurface.is_decorated()));
+        if rules.fullscreen.is_some() {
+            self.apply_fullscreen_rule(&window);
+        }
+    }
+
+    fn property_notify(&mut self, _xwm: XwmId, window: X11Surface, property: WmWindowProperty) {
+        if !matches!(
+            property,
+            WmWindowProperty::Title | WmWindowProperty::Class | WmWindowProperty::Hints
+        ) {
+            return;
+        }
+        let maybe = self
+            .space
+            .elements()
+            .chain(self.minimized.iter())
+            .find(|e| matches!(e.0.x11_surface(), Some(w) if w == &window))
+            .cloned();
+        let Some(elem) = maybe else {
+            return;
+        };
+        if matches!(property, WmWindowProperty::Hints) {
+            // the urgency hint of WM_HINTS, clients demanding attention set it
+            let urgent = window.hints().is_some_and(|hints| hints.urgent);
+            self.set_urgent(&elem, urgent);
+        } else {
+            self.apply_window_rules(&elem);
+        }
     }
 
     fn mapped_override_redirect_window(&mut self, _xwm: XwmId, window: X11Surface) {
@@ -81,10 +130,12 @@ impl<BackendData: Backend> XwmHandler for AnvilState<BackendData> {
         let maybe = self
             .space
             .elements()
+            .chain(self.minimized.iter())
+            .chain(self.scratchpads.values())
             .find(|e| matches!(e.0.x11_surface(), Some(w) if w == &window))
             .cloned();
         if let Some(elem) = maybe {
-            self.space.unmap_elem(&elem)
+            self.window_unmapped(&elem)
         }
         if !window.is_override_redirect() {
             window.set_mapped(false).unwrap();
@@ -129,7 +180,7 @@ impl<BackendData: Backend> XwmHandler for AnvilState<BackendData> {
         else {
             return;
         };
-        self.space.map_element(elem, geometry.loc, false);
+        map_with_transients(&mut self.space, &elem, geometry.loc, false);
         // TODO: We don't properly handle the order of override-redirect windows here,
         //       they are always mapped top and then never reordered.
     }
@@ -159,31 +210,38 @@ impl<BackendData: Backend> XwmHandler for AnvilState<BackendData> {
         }
     }
 
+    fn minimize_request(&mut self, _xwm: XwmId, window: X11Surface) {
+        let Some(elem) = self
+            .space
+            .elements()
+            .find(|e| matches!(e.0.x11_surface(), Some(w) if w == &window))
+            .cloned()
+        else {
+            return;
+        };
+        self.minimize_window(&elem);
+    }
+
+    fn unminimize_request(&mut self, _xwm: XwmId, window: X11Surface) {
+        let Some(elem) = self
+            .minimized
+            .iter()
+            .find(|e| matches!(e.0.x11_surface(), Some(w) if w == &window))
+            .cloned()
+        else {
+            return;
+        };
+        self.restore_window(&elem);
+    }
+
     fn fullscreen_request(&mut self, _xwm: XwmId, window: X11Surface) {
         if let Some(elem) = self
             .space
             .elements()
             .find(|e| matches!(e.0.x11_surface(), Some(w) if w == &window))
+            .cloned()
         {
-            let outputs_for_window = self.space.outputs_for_element(elem);
-            let output = outputs_for_window
-                .first()
-                // The window hasn't been mapped yet, use the primary output instead
-                .or_else(|| self.space.outputs().next())
-                // Assumes that at least one output exists
-                .expect("No outputs found");
-            let geometry = self.space.output_geometry(output).unwrap();
-
-            window.set_fullscreen(true).unwrap();
-            elem.set_ssd(false);
-            window.configure(geometry).unwrap();
-            output.user_data().insert_if_missing(FullscreenSurface::default);
-            output
-                .user_data()
-                .get::<FullscreenSurface>()
-                .unwrap()
-                .set(elem.clone());
-            trace!("Fullscreening: {:?}", elem);
+            self.fullscreen_window(&elem, None);
         }
     }
 
@@ -192,21 +250,9 @@ impl<BackendData: Backend> XwmHandler for AnvilState<BackendData> {
             .space
             .elements()
             .find(|e| matches!(e.0.x11_surface(), Some(w) if w == &window))
+            .cloned()
         {
-            window.set_fullscreen(false).unwrap();
-            elem.set_ssd(!window.is_decorated());
-            if let Some(output) = self.space.outputs().find(|o| {
-                o.user_data()
-                    .get::<FullscreenSurface>()
-                    .and_then(|f| f.get())
-                    .map(|w| &w == elem)
-                    .unwrap_or(false)
-            }) {
-                trace!("Unfullscreening: {:?}", elem);
-                output.user_data().get::<FullscreenSurface>().unwrap().clear();
-                window.configure(self.space.element_bbox(elem)).unwrap();
-                self.backend_data.reset_buffers(output);
-            }
+            self.unfullscreen_window(&elem);
         }
     }
 
@@ -425,3 +471,88 @@ impl<BackendData: Backend> AnvilState<BackendData> {
         pointer.set_grab(self, grab, SERIAL_COUNTER.next_serial(), Focus::Clear);
     }
 }
+
+/// `_NET_WM_STATE` client message actions
+const NET_WM_STATE_REMOVE: u32 = 0;
+const NET_WM_STATE_ADD: u32 = 1;
+const NET_WM_STATE_TOGGLE: u32 = 2;
+
+impl<BackendData: Backend> AnvilState<BackendData> {
+    /// Marks X11 windows urgent when they ask for `_NET_WM_STATE_DEMANDS_ATTENTION`.
+    ///
+    /// The X11 window manager ignores that state, so a second connection to Xwayland
+    /// listens for the client messages requesting it. They are sent to the root window
+    /// and reach every client selecting SubstructureNotify on it.
+    pub fn watch_attention_requests(&mut self, display_number: u32) {
+        let connect = || -> Result<_, Box<dyn std::error::Error>> {
+            let (conn, screen) = RustConnection::connect(Some(&format!(":{}", display_number)))?;
+            let root = conn.setup().roots[screen].root;
+            let net_wm_state = conn.intern_atom(false, b"_NET_WM_STATE")?.reply()?.atom;
+            let demands_attention = conn
+                .intern_atom(false, b"_NET_WM_STATE_DEMANDS_ATTENTION")?
+                .reply()?
+                .atom;
+            conn.change_window_attributes(
+                root,
+                &ChangeWindowAttributesAux::new().event_mask(EventMask::SUBSTRUCTURE_NOTIFY),
+            )?
+            .check()?;
+            let fd = conn.stream().as_fd().try_clone_to_owned()?;
+            Ok((conn, fd, net_wm_state, demands_attention))
+        };
+        let (conn, fd, net_wm_state, demands_attention) = match connect() {
+            Ok(watch) => watch,
+            Err(err) => {
+                warn!("Failed to watch X11 attention requests: {}", err);
+                return;
+            }
+        };
+
+        let source = Generic::new(fd, Interest::READ, Mode::Level);
+        let ret = self.handle.insert_source(source, move |_, _, state| loop {
+            let event = match conn.poll_for_event() {
+                Ok(Some(event)) => event,
+                Ok(None) => return Ok(PostAction::Continue),
+                Err(err) => {
+                    warn!("Lost the X11 connection watching attention requests: {}", err);
+                    return Ok(PostAction::Remove);
+                }
+            };
+            let Event::ClientMessage(message) = event else {
+                continue;
+            };
+            let [action, first, second, ..] = message.data.as_data32();
+            if message.type_ == net_wm_state && (first == demands_attention || second == demands_attention) {
+                state.attention_requested(message.window, action);
+            }
+        });
+        if let Err(err) = ret {
+            error!(
+                "Failed to insert the X11 attention source into the event loop: {}",
+                err
+            );
+        }
+    }
+
+    fn attention_requested(&mut self, window_id: u32, action: u32) {
+        let Some(window) = self
+            .space
+            .elements()
+            .chain(self.minimized.iter())
+            .find(|e| {
+                e.0.x11_surface()
+                    .is_some_and(|surface| surface.window_id() == window_id)
+            })
+            .cloned()
+        else {
+            return;
+        };
+        let urgent = match action {
+            NET_WM_STATE_REMOVE => false,
+            NET_WM_STATE_ADD => true,
+            NET_WM_STATE_TOGGLE => !window.is_urgent(),
+            _ => return,
+        };
+        self.set_urgent(&window, urgent);
+    }
+}
@@ -25,7 +25,7 @@ use smithay::{
         },
     },
 };
-use tracing::{trace, warn};
+use tracing::warn;
 
 use crate::{
     focus::KeyboardFocusTarget,
@@ -34,7 +34,7 @@ use crate::{
 };
 
 use super::{
-    fullscreen_output_geometry, place_new_window, FullscreenSurface, PointerMoveSurfaceGrab,
+    finish_floating_placement, map_with_transients, place_floating, place_new_window, PointerMoveSurfaceGrab,
     PointerResizeSurfaceGrab, ResizeData, ResizeEdge, ResizeState, SurfaceData, WindowElement,
 };
 
@@ -48,13 +48,46 @@ impl<BackendData: Backend> XdgShellHandler for AnvilState<BackendData> {
         // of a xdg_surface has to be sent during the commit if
         // the surface is not already configured
         let window = WindowElement(Window::new_wayland_window(surface.clone()));
-        place_new_window(&mut self.space, self.pointer.current_location(), &window, true);
+        // most clients only set their app_id and title later, the rules are evaluated again then
+        self.apply_window_rules(&window);
+        let output = self.new_window_output();
+        if self.pending_scratchpads.is_empty() {
+            place_new_window(&mut self.space, output.as_ref(), &window, true);
+        } else {
+            // the window may belong to a scratchpad, that is only known once it set its app_id
+            place_floating(&mut self.space, output.as_ref(), &window, true);
+        }
 
         compositor::add_post_commit_hook(surface.wl_surface(), |state: &mut Self, _, surface| {
             handle_toplevel_commit(&mut state.space, surface);
         });
     }
 
+    fn title_changed(&mut self, surface: ToplevelSurface) {
+        if let Some(window) = self.window_for_surface(surface.wl_surface()) {
+            self.apply_window_rules(&window);
+        }
+    }
+
+    fn app_id_changed(&mut self, surface: ToplevelSurface) {
+        if let Some(window) = self.window_for_surface(surface.wl_surface()) {
+            self.apply_window_rules(&window);
+        }
+    }
+
+    fn toplevel_destroyed(&mut self, surface: ToplevelSurface) {
+        let window = self
+            .space
+            .elements()
+            .chain(self.minimized.iter())
+            .chain(self.scratchpads.values())
+            .find(|w| w.0.toplevel() == Some(&surface))
+            .cloned();
+        if let Some(window) = window {
+            self.window_unmapped(&window);
+        }
+    }
+
     fn new_popup(&mut self, surface: PopupSurface, _positioner: PositionerState) {
         // Do not send a configure here, the initial configure
         // of a xdg_surface has to be sent during the commit if
@@ -263,110 +296,43 @@ impl<BackendData: Backend> XdgShellHandler for AnvilState<BackendData> {
         }
     }
 
-    fn fullscreen_request(&mut self, surface: ToplevelSurface, mut wl_output: Option<wl_output::WlOutput>) {
-        if surface
+    fn fullscreen_request(&mut self, surface: ToplevelSurface, wl_output: Option<wl_output::WlOutput>) {
+        let window = surface
             .current_state()
             .capabilities
             .contains(xdg_toplevel::WmCapabilities::Fullscreen)
-        {
-            // NOTE: This is only one part of the solution. We can set the
-            // location and configure size here, but the surface should be rendered fullscreen
-            // independently from its buffer size
-            let wl_surface = surface.wl_surface();
-
-            let output_geometry = fullscreen_output_geometry(wl_surface, wl_output.as_ref(), &mut self.space);
-
-            if let Some(geometry) = output_geometry {
-                let output = wl_output
-                    .as_ref()
-                    .and_then(Output::from_resource)
-                    .unwrap_or_else(|| self.space.outputs().next().unwrap().clone());
-                let client = match self.display_handle.get_client(wl_surface.id()) {
-                    Ok(client) => client,
-                    Err(_) => return,
-                };
-                for output in output.client_outputs(&client) {
-                    wl_output = Some(output);
+            .then(|| self.window_for_surface(surface.wl_surface()))
+            .flatten();
+        match window {
+            // if the requested output is not found the window's output is used
+            Some(window) => {
+                self.fullscreen_window(&window, wl_output.as_ref().and_then(Output::from_resource))
+            }
+            None => {
+                // The protocol demands us to always reply with a configure,
+                // regardless of we fulfilled the request or not
+                if surface.is_initial_configure_sent() {
+                    surface.send_configure();
                 }
-                let window = self
-                    .space
-                    .elements()
-                    .find(|window| window.wl_surface().map(|s| &*s == wl_surface).unwrap_or(false))
-                    .unwrap();
-
-                surface.with_pending_state(|state| {
-                    state.states.set(xdg_toplevel::State::Fullscreen);
-                    state.size = Some(geometry.size);
-                    state.fullscreen_output = wl_output;
-                });
-                output.user_data().insert_if_missing(FullscreenSurface::default);
-                output
-                    .user_data()
-                    .get::<FullscreenSurface>()
-                    .unwrap()
-                    .set(window.clone());
-                trace!("Fullscreening: {:?}", window);
             }
         }
-
-        // The protocol demands us to always reply with a configure,
-        // regardless of we fulfilled the request or not
-        if surface.is_initial_configure_sent() {
-            surface.send_configure();
-        } else {
-            // Will be sent during initial configure
-        }
     }
 
     fn unfullscreen_request(&mut self, surface: ToplevelSurface) {
-        if !surface
-            .current_state()
-            .states
-            .contains(xdg_toplevel::State::Fullscreen)
-        {
-            return;
+        if let Some(window) = self.window_for_surface(surface.wl_surface()) {
+            self.unfullscreen_window(&window);
         }
-
-        let ret = surface.with_pending_state(|state| {
-            state.states.unset(xdg_toplevel::State::Fullscreen);
-            state.size = None;
-            state.fullscreen_output.take()
-        });
-        if let Some(output) = ret {
-            let output = Output::from_resource(&output).unwrap();
-            if let Some(fullscreen) = output.user_data().get::<FullscreenSurface>() {
-                trace!("Unfullscreening: {:?}", fullscreen.get());
-                fullscreen.clear();
-                self.backend_data.reset_buffers(&output);
-            }
-        }
-
-        surface.send_pending_configure();
     }
 
     fn maximize_request(&mut self, surface: ToplevelSurface) {
-        // NOTE: This should use layer-shell when it is implemented to
-        // get the correct maximum size
         if surface
             .current_state()
             .capabilities
             .contains(xdg_toplevel::WmCapabilities::Maximize)
         {
-            let window = self.window_for_surface(surface.wl_surface()).unwrap();
-            let outputs_for_window = self.space.outputs_for_element(&window);
-            let output = outputs_for_window
-                .first()
-                // The window hasn't been mapped yet, use the primary output instead
-                .or_else(|| self.space.outputs().next())
-                // Assumes that at least one output exists
-                .expect("No outputs found");
-            let geometry = self.space.output_geometry(output).unwrap();
-
-            surface.with_pending_state(|state| {
-                state.states.set(xdg_toplevel::State::Maximized);
-                state.size = Some(geometry.size);
-            });
-            self.space.map_element(window, geometry.loc, true);
+            if let Some(window) = self.window_for_surface(surface.wl_surface()) {
+                self.maximize_xdg(&surface, &window);
+            }
         }
 
         // The protocol demands us to always reply with a configure,
@@ -378,6 +344,12 @@ impl<BackendData: Backend> XdgShellHandler for AnvilState<BackendData> {
         }
     }
 
+    fn minimize_request(&mut self, surface: ToplevelSurface) {
+        if let Some(window) = self.window_for_surface(surface.wl_surface()) {
+            self.minimize_window(&window);
+        }
+    }
+
     fn unmaximize_request(&mut self, surface: ToplevelSurface) {
         if !surface
             .current_state()
@@ -387,10 +359,13 @@ impl<BackendData: Backend> XdgShellHandler for AnvilState<BackendData> {
             return;
         }
 
-        surface.with_pending_state(|state| {
-            state.states.unset(xdg_toplevel::State::Maximized);
-            state.size = None;
-        });
+        match self.window_for_surface(surface.wl_surface()) {
+            Some(window) => self.unmaximize_xdg(&surface, &window),
+            None => surface.with_pending_state(|state| {
+                state.states.unset(xdg_toplevel::State::Maximized);
+                state.size = None;
+            }),
+        }
         surface.send_pending_configure();
     }
 
@@ -471,10 +446,8 @@ impl<BackendData: Backend> AnvilState<BackendData> {
                 // If surface is maximized then unmaximize it
                 let current_state = surface.current_state();
                 if current_state.states.contains(xdg_toplevel::State::Maximized) {
-                    surface.with_pending_state(|state| {
-                        state.states.unset(xdg_toplevel::State::Maximized);
-                        state.size = None;
-                    });
+                    // the size and cell from before come back, the window follows the touch point
+                    self.restore_unmaximized(surface, &window);
 
                     surface.send_configure();
 
@@ -535,10 +508,8 @@ impl<BackendData: Backend> AnvilState<BackendData> {
         // If surface is maximized then unmaximize it
         let current_state = surface.current_state();
         if current_state.states.contains(xdg_toplevel::State::Maximized) {
-            surface.with_pending_state(|state| {
-                state.states.unset(xdg_toplevel::State::Maximized);
-                state.size = None;
-            });
+            // the size and cell from before come back, the window follows the pointer
+            self.restore_unmaximized(surface, &window);
 
             surface.send_configure();
 
@@ -609,6 +580,8 @@ fn handle_toplevel_commit(space: &mut Space<WindowElement>, surface: &WlSurface)
         .find(|w| w.wl_surface().as_deref() == Some(surface))
         .cloned()?;
 
+    finish_floating_placement(space, &window);
+
     let mut window_loc = space.element_location(&window)?;
     let geometry = window.geometry();
 
@@ -647,7 +620,7 @@ fn handle_toplevel_commit(space: &mut Space<WindowElement>, surface: &WlSurface)
 
     if new_loc.x.is_some() || new_loc.y.is_some() {
         // If TOP or LEFT side of the window got resized, we have to move it
-        space.map_element(window, window_loc, false);
+        map_with_transients(space, &window, window_loc, false);
     }
 
     Some(())
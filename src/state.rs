@@ -1,6 +1,7 @@
 use std::{
     collections::HashMap,
     os::unix::io::OwnedFd,
+    path::PathBuf,
     sync::{atomic::AtomicBool, Arc},
     time::Duration,
 };
@@ -35,7 +36,11 @@ use smithay::{
     },
     output::Output,
     reexports::{
-        calloop::{generic::Generic, Interest, LoopHandle, Mode, PostAction},
+        calloop::{
+            generic::Generic,
+            timer::{TimeoutAction, Timer},
+            Interest, LoopHandle, Mode, PostAction, RegistrationToken,
+        },
         wayland_protocols::xdg::decoration::{
             self as xdg_decoration, zv1::server::zxdg_toplevel_decoration_v1::Mode as DecorationMode,
         },
@@ -52,6 +57,7 @@ use smithay::{
         dmabuf::DmabufFeedback,
         fifo::{FifoBarrierCachedState, FifoManagerState},
         fractional_scale::{with_fractional_scale, FractionalScaleHandler, FractionalScaleManagerState},
+        idle_notify::{IdleNotifierHandler, IdleNotifierState},
         input_method::{InputMethodHandler, InputMethodManagerState, PopupSurface},
         keyboard_shortcuts_inhibit::{
             KeyboardShortcutsInhibitHandler, KeyboardShortcutsInhibitState, KeyboardShortcutsInhibitor,
@@ -98,8 +104,10 @@ use smithay::{
 #[cfg(feature = "xwayland")]
 use crate::cursor::Cursor;
 use crate::{
+    config::{config_path, load_config, GLOBAL_CONFIG},
     focus::{KeyboardFocusTarget, PointerFocusTarget},
-    shell::WindowElement,
+    ipc::IpcSocket,
+    shell::{Overview, PanGesture, Switcher, WindowElement, SAVE_INTERVAL},
 };
 #[cfg(feature = "xwayland")]
 use smithay::{
@@ -127,6 +135,8 @@ impl ClientData for ClientState {
 pub struct AnvilState<BackendData: Backend + 'static> {
     pub backend_data: BackendData,
     pub socket_name: Option<String>,
+    /// Socket answering queries about the compositor state, see `crate::ipc`
+    pub ipc_socket: Option<IpcSocket>,
     pub display_handle: DisplayHandle,
     pub running: Arc<AtomicBool>,
     pub handle: LoopHandle<'static, AnvilState<BackendData>>,
@@ -158,6 +168,7 @@ pub struct AnvilState<BackendData: Backend + 'static> {
     pub single_pixel_buffer_state: SinglePixelBufferState,
     pub fifo_manager_state: FifoManagerState,
     pub commit_timing_manager_state: CommitTimingManagerState,
+    pub idle_notifier_state: IdleNotifierState<AnvilState<BackendData>>,
 
     pub dnd_icon: Option<DndIcon>,
 
@@ -168,6 +179,11 @@ pub struct AnvilState<BackendData: Backend + 'static> {
     pub seat: Seat<AnvilState<BackendData>>,
     pub clock: Clock<Monotonic>,
     pub pointer: PointerHandle<AnvilState<BackendData>>,
+    pub pan_gesture: Option<PanGesture>,
+    /// Timer stepping the viewport scroll animations, while any is running
+    pub scroll_timer: Option<RegistrationToken>,
+    /// Timer blinking the highlight of urgent windows, while any window is urgent
+    pub blink_timer: Option<RegistrationToken>,
 
     #[cfg(feature = "xwayland")]
     pub xwm: Option<X11Wm>,
@@ -177,7 +193,20 @@ pub struct AnvilState<BackendData: Backend + 'static> {
     #[cfg(feature = "debug")]
     pub renderdoc: Option<renderdoc::RenderDoc<renderdoc::V141>>,
 
-    pub show_window_preview: bool,
+    pub overview: Option<Overview>,
+    pub switcher: Option<Switcher>,
+    /// Windows in the order they last had keyboard focus, most recent first
+    pub focus_stack: Vec<WindowElement>,
+    /// Minimized windows, in the order they were minimized
+    pub minimized: Vec<WindowElement>,
+    /// Windows adopted by a scratchpad, by scratchpad name
+    pub scratchpads: HashMap<String, WindowElement>,
+    /// Scratchpads whose command was started, waiting for their window to map
+    pub pending_scratchpads: Vec<String>,
+    /// Window the pointer is in, for focus following the pointer
+    pub hovered_window: Option<WindowElement>,
+    /// Pending delayed focus of the hovered window
+    pub hover_focus_timer: Option<RegistrationToken>,
 }
 
 #[derive(Debug)]
@@ -294,6 +323,21 @@ impl<BackendData: Backend> SeatHandler for AnvilState<BackendData> {
         let focus = wl_surface.and_then(|s| dh.get_client(s.id()).ok());
         set_data_device_focus(dh, seat, focus.clone());
         set_primary_focus(dh, seat, focus);
+
+        let element = match target {
+            Some(KeyboardFocusTarget::Window(window)) => {
+                self.space.elements().find(|elem| elem.0 == *window).cloned()
+            }
+            _ => None,
+        };
+        self.mark_focused(element.as_ref());
+        if let Some(element) = element {
+            self.set_urgent(&element, false);
+            self.focus_stack.retain(|window| window != &element);
+            self.focus_stack.insert(0, element.clone());
+            let mode = crate::config::layout().follow_focus;
+            self.scroll_to_window(&element, mode);
+        }
     }
     fn cursor_image(&mut self, _seat: &Seat<Self>, image: CursorImageStatus) {
         self.cursor_status = image;
@@ -405,16 +449,8 @@ impl<BackendData: Backend> XdgActivationHandler for AnvilState<BackendData> {
     }
 
     fn token_created(&mut self, _token: XdgActivationToken, data: XdgActivationTokenData) -> bool {
-        if let Some((serial, seat)) = data.serial {
-            let keyboard = self.seat.get_keyboard().unwrap();
-            Seat::from_resource(&seat) == Some(self.seat.clone())
-                && keyboard
-                    .last_enter()
-                    .map(|last_enter| serial.is_no_older_than(&last_enter))
-                    .unwrap_or(false)
-        } else {
-            false
-        }
+        self.validate_activation_token(&data);
+        true
     }
 
     fn request_activation(
@@ -423,16 +459,25 @@ impl<BackendData: Backend> XdgActivationHandler for AnvilState<BackendData> {
         token_data: XdgActivationTokenData,
         surface: WlSurface,
     ) {
-        if token_data.timestamp.elapsed().as_secs() < 10 {
-            // Just grant the wish
-            let w = self
-                .space
-                .elements()
-                .find(|window| window.wl_surface().map(|s| *s == surface).unwrap_or(false))
-                .cloned();
-            if let Some(window) = w {
-                self.space.raise_element(&window, true);
+        let window = self.window_for_surface(&surface).or_else(|| {
+            self.minimized
+                .iter()
+                .find(|window| window.wl_surface().is_some_and(|s| *s == surface))
+                .cloned()
+        });
+        let Some(window) = window else {
+            return;
+        };
+        if self.activation_granted(&window, &token_data) {
+            if self.minimized.contains(&window) {
+                self.restore_window(&window);
+                return;
             }
+            self.focus_window(&window);
+            self.reveal_window(&window);
+        } else {
+            // not allowed to take the focus, ask for attention instead
+            self.set_urgent(&window, true);
         }
     }
 }
@@ -441,17 +486,25 @@ delegate_xdg_activation!(@<BackendData: Backend + 'static> AnvilState<BackendDat
 impl<BackendData: Backend> XdgDecorationHandler for AnvilState<BackendData> {
     fn new_decoration(&mut self, toplevel: ToplevelSurface) {
         use xdg_decoration::zv1::server::zxdg_toplevel_decoration_v1::Mode;
-        // Set the default to client side
+        // Set the default to client side, unless a window rule asks for server side decorations
+        let ssd = self
+            .window_for_surface(toplevel.wl_surface())
+            .and_then(|window| window.rule_properties().ssd)
+            .unwrap_or(false);
         toplevel.with_pending_state(|state| {
-            state.decoration_mode = Some(Mode::ClientSide);
+            state.decoration_mode = Some(if ssd { Mode::ServerSide } else { Mode::ClientSide });
         });
     }
     fn request_mode(&mut self, toplevel: ToplevelSurface, mode: DecorationMode) {
         use xdg_decoration::zv1::server::zxdg_toplevel_decoration_v1::Mode;
 
+        // window rules win over the client's wish
+        let forced = self
+            .window_for_surface(toplevel.wl_surface())
+            .and_then(|window| window.rule_properties().ssd);
         toplevel.with_pending_state(|state| {
-            state.decoration_mode = Some(match mode {
-                DecorationMode::ServerSide => Mode::ServerSide,
+            state.decoration_mode = Some(match (forced, mode) {
+                (Some(true), _) | (None, DecorationMode::ServerSide) => Mode::ServerSide,
                 _ => Mode::ClientSide,
             });
         });
@@ -575,6 +628,13 @@ smithay::delegate_fifo!(@<BackendData: Backend + 'static> AnvilState<BackendData
 
 smithay::delegate_commit_timing!(@<BackendData: Backend + 'static> AnvilState<BackendData>);
 
+impl<BackendData: Backend> IdleNotifierHandler for AnvilState<BackendData> {
+    fn idle_notifier_state(&mut self) -> &mut IdleNotifierState<Self> {
+        &mut self.idle_notifier_state
+    }
+}
+smithay::delegate_idle_notify!(@<BackendData: Backend + 'static> AnvilState<BackendData>);
+
 impl<BackendData: Backend + 'static> AnvilState<BackendData> {
     pub fn init(
         display: Display<AnvilState<BackendData>>,
@@ -586,6 +646,9 @@ impl<BackendData: Backend + 'static> AnvilState<BackendData> {
 
         let clock = Clock::new();
 
+        let config_path = config_path();
+        *GLOBAL_CONFIG.write().unwrap() = Some(load_config(&config_path));
+
         // init wayland clients
         let socket_name = if listen_on_socket {
             let source = ListeningSocketSource::new_auto().unwrap();
@@ -605,6 +668,9 @@ impl<BackendData: Backend + 'static> AnvilState<BackendData> {
         } else {
             None
         };
+        let ipc_socket = socket_name
+            .as_deref()
+            .and_then(|socket_name| crate::ipc::listen(&handle, socket_name));
         handle
             .insert_source(
                 Generic::new(display, Interest::READ, Mode::Level),
@@ -618,6 +684,12 @@ impl<BackendData: Backend + 'static> AnvilState<BackendData> {
                 },
             )
             .expect("Failed to init wayland server source");
+        handle
+            .insert_source(Timer::from_duration(SAVE_INTERVAL), |_, _, state| {
+                state.flush_layout();
+                TimeoutAction::ToDuration(SAVE_INTERVAL)
+            })
+            .expect("Failed to init board layout saving");
 
         // init globals
         let compositor_state = CompositorState::new::<Self>(&dh);
@@ -639,6 +711,7 @@ impl<BackendData: Backend + 'static> AnvilState<BackendData> {
         let single_pixel_buffer_state = SinglePixelBufferState::new::<Self>(&dh);
         let fifo_manager_state = FifoManagerState::new::<Self>(&dh);
         let commit_timing_manager_state = CommitTimingManagerState::new::<Self>(&dh);
+        let idle_notifier_state = IdleNotifierState::new(&dh, handle.clone());
         TextInputManagerState::new::<Self>(&dh);
         InputMethodManagerState::new::<Self, _>(&dh, |_client| true);
         VirtualKeyboardManagerState::new::<Self, _>(&dh, |_client| true);
@@ -677,8 +750,10 @@ impl<BackendData: Backend + 'static> AnvilState<BackendData> {
             backend_data,
             display_handle: dh,
             socket_name,
+            ipc_socket,
             running: Arc::new(AtomicBool::new(true)),
             handle,
+            config_path,
             space: Space::default(),
             popups: PopupManager::default(),
             compositor_state,
@@ -700,12 +775,16 @@ impl<BackendData: Backend + 'static> AnvilState<BackendData> {
             single_pixel_buffer_state,
             fifo_manager_state,
             commit_timing_manager_state,
+            idle_notifier_state,
             dnd_icon: None,
             suppressed_keys: Vec::new(),
             cursor_status: CursorImageStatus::default_named(),
             seat_name,
             seat,
             pointer,
+            pan_gesture: None,
+            scroll_timer: None,
+            blink_timer: None,
             clock,
 
             #[cfg(feature = "xwayland")]
@@ -716,7 +795,14 @@ impl<BackendData: Backend + 'static> AnvilState<BackendData> {
             xdisplay: None,
             #[cfg(feature = "debug")]
             renderdoc: renderdoc::RenderDoc::new().ok(),
-            show_window_preview: false,
+            overview: None,
+            switcher: None,
+            focus_stack: Vec::new(),
+            minimized: Vec::new(),
+            scratchpads: HashMap::new(),
+            pending_scratchpads: Vec::new(),
+            hovered_window: None,
+            hover_focus_timer: None,
         }
     }
 
@@ -763,6 +849,7 @@ impl<BackendData: Backend + 'static> AnvilState<BackendData> {
                     .expect("Failed to set xwayland default cursor");
                     data.xwm = Some(wm);
                     data.xdisplay = Some(display_number);
+                    data.watch_attention_requests(display_number);
                 }
                 XWaylandEvent::Error => {
                     warn!("XWayland crashed on startup");
@@ -774,6 +861,27 @@ impl<BackendData: Backend + 'static> AnvilState<BackendData> {
     }
 }
 
+impl<BackendData: Backend> AnvilState<BackendData> {
+    /// Environment variables pointing spawned programs at this compositor
+    pub fn client_env(&self) -> impl Iterator<Item = (&'static str, String)> {
+        self.socket_name
+            .clone()
+            .map(|v| ("WAYLAND_DISPLAY", v))
+            .into_iter()
+            .chain(
+                #[cfg(feature = "xwayland")]
+                self.xdisplay.map(|v| ("DISPLAY", format!(":{}", v))),
+                #[cfg(not(feature = "xwayland"))]
+                None,
+            )
+            .chain(
+                self.ipc_socket
+                    .as_ref()
+                    .map(|socket| ("ANVIL_SOCKET", socket.path().to_string_lossy().into_owned())),
+            )
+    }
+}
+
 impl<BackendData: Backend + 'static> AnvilState<BackendData> {
     pub fn pre_repaint(&mut self, output: &Output, frame_target: impl Into<Time<Monotonic>>) {
         let frame_target = frame_target.into();
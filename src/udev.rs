@@ -9,7 +9,7 @@ use std::{
 use crate::{
     drawing::*,
     render::*,
-    shell::WindowElement,
+    shell::{Overview, Switcher, WindowElement},
     state::{take_presentation_feedback, update_primary_scanout_output, AnvilState, Backend},
 };
 use crate::{
@@ -42,8 +42,8 @@ use smithay::{
         renderer::{
             damage::Error as OutputDamageTrackerError,
             element::{memory::MemoryRenderBuffer, AsRenderElements, RenderElementStates},
-            gles::GlesRenderer,
-            multigpu::{gbm::GbmGlesBackend, GpuManager, MultiRenderer},
+            gles::{GlesError, GlesFrame, GlesRenderer},
+            multigpu::{gbm::GbmGlesBackend, GpuManager, MultiError, MultiRenderer},
             DebugFlags, ImportDma, ImportMemWl,
         },
         session::{
@@ -118,6 +118,19 @@ type UdevRenderer<'a> = MultiRenderer<
     GbmGlesBackend<GlesRenderer, DrmDeviceFd>,
 >;
 
+impl EffectsRenderer for UdevRenderer<'_> {
+    fn effect_shaders(&mut self) -> Option<EffectShaders> {
+        EffectShaders::get(self.as_mut())
+    }
+
+    fn with_gles_frame<'frame, 'buffer>(
+        frame: &mut Self::Frame<'frame, 'buffer>,
+        draw: impl FnOnce(&mut GlesFrame<'frame, 'buffer>) -> Result<(), GlesError>,
+    ) -> Result<(), Self::Error> {
+        draw(frame.as_mut()).map_err(MultiError::Render)
+    }
+}
+
 #[derive(Debug, PartialEq)]
 struct UdevOutputId {
     device_id: DrmNode,
@@ -499,6 +512,9 @@ pub fn run_udev() {
             display_handle.flush_clients().unwrap();
         }
     }
+
+    // the last layout change may not have been written yet
+    state.flush_layout();
 }
 
 impl DrmLeaseHandler for AnvilState<UdevData> {
@@ -1345,7 +1361,8 @@ impl AnvilState<UdevData> {
             &mut self.backend_data.pointer_element,
             &self.dnd_icon,
             &mut self.cursor_status,
-            self.show_window_preview,
+            self.overview.as_ref(),
+            self.switcher.as_ref(),
         );
         let reschedule = match result {
             Ok((has_rendered, states)) => {
@@ -1427,7 +1444,8 @@ fn render_surface<'a>(
     pointer_element: &mut PointerElement,
     dnd_icon: &Option<DndIcon>,
     cursor_status: &mut CursorImageStatus,
-    show_window_preview: bool,
+    overview: Option<&Overview>,
+    switcher: Option<&Switcher>,
 ) -> Result<(bool, RenderElementStates), SwapBuffersError> {
     let output_geometry = space.output_geometry(output).unwrap();
     let scale = Scale::from(output.current_scale().fractional_scale());
@@ -1505,7 +1523,7 @@ fn render_surface<'a>(
     }
 
     let (elements, clear_color) =
-        output_elements(output, space, custom_elements, renderer, show_window_preview);
+        output_elements(output, space, custom_elements, renderer, overview, switcher);
 
     let frame_mode = if surface.disable_direct_scanout {
         FrameFlags::empty()
@@ -269,7 +269,8 @@ pub fn run_winit() {
             *full_redraw = full_redraw.saturating_sub(1);
             let space = &mut state.space;
             let damage_tracker = &mut state.backend_data.damage_tracker;
-            let show_window_preview = state.show_window_preview;
+            let overview = state.overview.as_ref();
+            let switcher = state.switcher.as_ref();
 
             let dnd_icon = state.dnd_icon.as_ref();
 
@@ -355,7 +356,8 @@ pub fn run_winit() {
                     &mut fb,
                     damage_tracker,
                     age,
-                    show_window_preview,
+                    overview,
+                    switcher,
                 )
                 .map_err(|err| match err {
                     OutputDamageTrackerError::Rendering(err) => err.into(),
@@ -452,4 +454,7 @@ pub fn run_winit() {
         #[cfg(feature = "debug")]
         state.backend_data.fps.tick();
     }
+
+    // the last layout change may not have been written yet
+    state.flush_layout();
 }
@@ -400,7 +400,8 @@ pub fn run_x11() {
                 &mut fb,
                 &mut backend_data.damage_tracker,
                 age.into(),
-                state.show_window_preview,
+                state.overview.as_ref(),
+                state.switcher.as_ref(),
             );
 
             match render_res {
@@ -483,4 +484,7 @@ pub fn run_x11() {
             display_handle.flush_clients().unwrap();
         }
     }
+
+    // the last layout change may not have been written yet
+    state.flush_layout();
 }